@@ -0,0 +1,319 @@
+//! Typed values provided by the model that are not part of a `Sounding`.
+
+use metfor::{
+    Celsius, CelsiusDiff, HectoPascal, JpKg, Kelvin, Km, MetersPSec, Mm, Quantity, WindUV,
+};
+use optional::{none, some, Optioned};
+use sounding_analysis::PrecipType;
+use std::collections::HashMap;
+
+use self::keys::*;
+
+/// The indexes and other values provided by the model that are not part of a `Sounding`.
+///
+/// This carries the same information as the `HashMap<&'static str, f64>` returned alongside each
+/// `Sounding`, but with units, and without the need to know the keys. Converting to and from the
+/// `HashMap` is lossless.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProviderAnalysis {
+    /// Showalter index
+    pub showalter: Optioned<CelsiusDiff>,
+    /// Severe Weather Threat index
+    pub swet: Optioned<f64>,
+    /// K-index
+    pub k_index: Optioned<Celsius>,
+    /// Lifted index
+    pub lifted_index: Optioned<CelsiusDiff>,
+    /// Lifting condensation level pressure
+    pub lcl_pressure: Optioned<HectoPascal>,
+    /// Precipitable water
+    pub pwat: Optioned<Mm>,
+    /// Total-Totals
+    pub total_totals: Optioned<f64>,
+    /// Convective available potential energy
+    pub cape: Optioned<JpKg>,
+    /// Convective inhibition
+    pub cin: Optioned<JpKg>,
+    /// Temperature at the lifting condensation level
+    pub lcl_temperature: Optioned<Kelvin>,
+    /// Equilibrium level pressure
+    pub equilibrium_level: Optioned<HectoPascal>,
+    /// Level of free convection pressure
+    pub lfc: Optioned<HectoPascal>,
+    /// Bulk Richardson number
+    pub bulk_richardson_number: Optioned<f64>,
+
+    /// Skin temperature
+    pub skin_temperature: Optioned<Celsius>,
+    /// Layer 1 soil temperature
+    pub layer_1_soil_temperature: Optioned<Kelvin>,
+    /// 1-hour accumulated snowfall (Kg/m**2)
+    pub snow_1hr: Optioned<f64>,
+    /// 1-hour total precipitation
+    pub precip_1hr: Optioned<Mm>,
+    /// 1-hour convective precipitation
+    pub convective_precip_1hr: Optioned<Mm>,
+    /// Layer 2 soil temperature
+    pub layer_2_soil_temperature: Optioned<Kelvin>,
+    /// Snow ratio from explicit cloud scheme (percent)
+    pub snow_ratio: Optioned<f64>,
+    /// Visibility
+    pub visibility: Optioned<Km>,
+    /// Storm relative helicity (m**2/s**2)
+    pub storm_relative_helicity: Optioned<f64>,
+    /// Storm motion
+    pub storm_motion: Optioned<WindUV<MetersPSec>>,
+    /// Weather type derived from the model weather symbol and precipitation type flags.
+    pub wx_symbol_code: Option<PrecipType>,
+}
+
+/// The keys of the `HashMap<&'static str, f64>` returned alongside each `Sounding`.
+pub mod keys {
+    /// Showalter index
+    pub const SHOWALTER: &str = "Showalter";
+    /// Severe Weather Threat index
+    pub const SWET: &str = "SWeT";
+    /// K-index
+    pub const K_INDEX: &str = "K";
+    /// Lifted index
+    pub const LIFTED_INDEX: &str = "LI";
+    /// Lifting condensation level pressure (hPa)
+    pub const LCL_PRESSURE: &str = "LCL";
+    /// Precipitable water (mm)
+    pub const PWAT: &str = "PWAT";
+    /// Total-Totals
+    pub const TOTAL_TOTALS: &str = "TotalTotals";
+    /// Convective available potential energy (J/kg)
+    pub const CAPE: &str = "CAPE";
+    /// Convective inhibition (J/kg)
+    pub const CIN: &str = "CIN";
+    /// Temperature at the lifting condensation level (K)
+    pub const LCL_TEMPERATURE: &str = "LCLTemperature";
+    /// Equilibrium level pressure (hPa)
+    pub const EQUILIBRIUM_LEVEL: &str = "EquilibriumLevel";
+    /// Level of free convection pressure (hPa)
+    pub const LFC: &str = "LFC";
+    /// Bulk Richardson number
+    pub const BULK_RICHARDSON_NUMBER: &str = "BulkRichardsonNumber";
+    /// Skin temperature (C)
+    pub const SKIN_TEMPERATURE: &str = "SkinTemperature";
+    /// Layer 1 soil temperature (K)
+    pub const LAYER_1_SOIL_TEMPERATURE: &str = "Layer1SoilTemp";
+    /// 1-hour accumulated snowfall (Kg/m**2)
+    pub const SNOW_1HR: &str = "SnowFall1HourKgPerMeterSquared";
+    /// 1-hour total precipitation (mm)
+    pub const PRECIP_1HR: &str = "Precipitation1HrMm";
+    /// 1-hour convective precipitation (mm)
+    pub const CONVECTIVE_PRECIP_1HR: &str = "ConvectivePrecip1HrMm";
+    /// Layer 2 soil temperature (K)
+    pub const LAYER_2_SOIL_TEMPERATURE: &str = "Layer2SoilTemp";
+    /// Snow ratio from explicit cloud scheme (percent)
+    pub const SNOW_RATIO: &str = "SnowRatio";
+    /// Visibility (km)
+    pub const VISIBILITY: &str = "VisibilityKm";
+    /// Storm relative helicity (m**2/s**2)
+    pub const STORM_RELATIVE_HELICITY: &str = "StormRelativeHelicity";
+    /// Storm motion u component (m/s)
+    pub const STORM_MOTION_U: &str = "StormMotionUMps";
+    /// Storm motion v component (m/s)
+    pub const STORM_MOTION_V: &str = "StormMotionVMps";
+    /// Weather type code, see `sounding_analysis::PrecipType`
+    pub const WX_SYMBOL_CODE: &str = "WxSymbolCode";
+}
+
+/// A typed name for each key of the `HashMap<&'static str, f64>` returned alongside each
+/// `Sounding`, so the keys don't have to be typed out as strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnalysisKey {
+    /// Showalter index
+    Showalter,
+    /// Severe Weather Threat index
+    Swet,
+    /// K-index
+    KIndex,
+    /// Lifted index
+    LiftedIndex,
+    /// Lifting condensation level pressure (hPa)
+    LclPressure,
+    /// Precipitable water (mm)
+    Pwat,
+    /// Total-Totals
+    TotalTotals,
+    /// Convective available potential energy (J/kg)
+    Cape,
+    /// Convective inhibition (J/kg)
+    Cin,
+    /// Temperature at the lifting condensation level (K)
+    LclTemperature,
+    /// Equilibrium level pressure (hPa)
+    EquilibriumLevel,
+    /// Level of free convection pressure (hPa)
+    Lfc,
+    /// Bulk Richardson number
+    BulkRichardsonNumber,
+    /// Skin temperature (C)
+    SkinTemperature,
+    /// Layer 1 soil temperature (K)
+    Layer1SoilTemperature,
+    /// 1-hour accumulated snowfall (Kg/m**2)
+    Snow1Hr,
+    /// 1-hour total precipitation (mm)
+    Precip1Hr,
+    /// 1-hour convective precipitation (mm)
+    ConvectivePrecip1Hr,
+    /// Layer 2 soil temperature (K)
+    Layer2SoilTemperature,
+    /// Snow ratio from explicit cloud scheme (percent)
+    SnowRatio,
+    /// Visibility (km)
+    Visibility,
+    /// Storm relative helicity (m**2/s**2)
+    StormRelativeHelicity,
+    /// Storm motion u component (m/s)
+    StormMotionU,
+    /// Storm motion v component (m/s)
+    StormMotionV,
+    /// Weather type code, see `sounding_analysis::PrecipType`
+    WxSymbolCode,
+}
+
+impl AnalysisKey {
+    /// Get the key used in the `HashMap`, one of the constants in `keys`.
+    pub fn name(self) -> &'static str {
+        match self {
+            AnalysisKey::Showalter => SHOWALTER,
+            AnalysisKey::Swet => SWET,
+            AnalysisKey::KIndex => K_INDEX,
+            AnalysisKey::LiftedIndex => LIFTED_INDEX,
+            AnalysisKey::LclPressure => LCL_PRESSURE,
+            AnalysisKey::Pwat => PWAT,
+            AnalysisKey::TotalTotals => TOTAL_TOTALS,
+            AnalysisKey::Cape => CAPE,
+            AnalysisKey::Cin => CIN,
+            AnalysisKey::LclTemperature => LCL_TEMPERATURE,
+            AnalysisKey::EquilibriumLevel => EQUILIBRIUM_LEVEL,
+            AnalysisKey::Lfc => LFC,
+            AnalysisKey::BulkRichardsonNumber => BULK_RICHARDSON_NUMBER,
+            AnalysisKey::SkinTemperature => SKIN_TEMPERATURE,
+            AnalysisKey::Layer1SoilTemperature => LAYER_1_SOIL_TEMPERATURE,
+            AnalysisKey::Snow1Hr => SNOW_1HR,
+            AnalysisKey::Precip1Hr => PRECIP_1HR,
+            AnalysisKey::ConvectivePrecip1Hr => CONVECTIVE_PRECIP_1HR,
+            AnalysisKey::Layer2SoilTemperature => LAYER_2_SOIL_TEMPERATURE,
+            AnalysisKey::SnowRatio => SNOW_RATIO,
+            AnalysisKey::Visibility => VISIBILITY,
+            AnalysisKey::StormRelativeHelicity => STORM_RELATIVE_HELICITY,
+            AnalysisKey::StormMotionU => STORM_MOTION_U,
+            AnalysisKey::StormMotionV => STORM_MOTION_V,
+            AnalysisKey::WxSymbolCode => WX_SYMBOL_CODE,
+        }
+    }
+}
+
+/// Get the value for `key` from the `HashMap` returned alongside each `Sounding`, or `None` if it
+/// is missing.
+pub fn get_index(map: &HashMap<&'static str, f64>, key: AnalysisKey) -> Option<f64> {
+    map.get(key.name()).copied()
+}
+
+impl From<&HashMap<&'static str, f64>> for ProviderAnalysis {
+    fn from(map: &HashMap<&'static str, f64>) -> Self {
+        let get = |key: &str| -> Optioned<f64> {
+            match map.get(key) {
+                Some(&val) => some(val),
+                None => none(),
+            }
+        };
+
+        let storm_motion = get(STORM_MOTION_U).and_then(|u| {
+            get(STORM_MOTION_V).map_t(|v| WindUV {
+                u: MetersPSec(u),
+                v: MetersPSec(v),
+            })
+        });
+
+        ProviderAnalysis {
+            showalter: get(SHOWALTER).map_t(CelsiusDiff),
+            swet: get(SWET),
+            k_index: get(K_INDEX).map_t(Celsius),
+            lifted_index: get(LIFTED_INDEX).map_t(CelsiusDiff),
+            lcl_pressure: get(LCL_PRESSURE).map_t(HectoPascal),
+            pwat: get(PWAT).map_t(Mm),
+            total_totals: get(TOTAL_TOTALS),
+            cape: get(CAPE).map_t(JpKg),
+            cin: get(CIN).map_t(JpKg),
+            lcl_temperature: get(LCL_TEMPERATURE).map_t(Kelvin),
+            equilibrium_level: get(EQUILIBRIUM_LEVEL).map_t(HectoPascal),
+            lfc: get(LFC).map_t(HectoPascal),
+            bulk_richardson_number: get(BULK_RICHARDSON_NUMBER),
+            skin_temperature: get(SKIN_TEMPERATURE).map_t(Celsius),
+            layer_1_soil_temperature: get(LAYER_1_SOIL_TEMPERATURE).map_t(Kelvin),
+            snow_1hr: get(SNOW_1HR),
+            precip_1hr: get(PRECIP_1HR).map_t(Mm),
+            convective_precip_1hr: get(CONVECTIVE_PRECIP_1HR).map_t(Mm),
+            layer_2_soil_temperature: get(LAYER_2_SOIL_TEMPERATURE).map_t(Kelvin),
+            snow_ratio: get(SNOW_RATIO),
+            visibility: get(VISIBILITY).map_t(Km),
+            storm_relative_helicity: get(STORM_RELATIVE_HELICITY),
+            storm_motion,
+            wx_symbol_code: get(WX_SYMBOL_CODE)
+                .into_option()
+                .map(|code| PrecipType::from(code as u8)),
+        }
+    }
+}
+
+impl From<&ProviderAnalysis> for HashMap<&'static str, f64> {
+    fn from(anal: &ProviderAnalysis) -> Self {
+        macro_rules! check_and_add {
+            ($opt:expr, $key:expr, $hash_map:ident) => {
+                if let Some(val) = $opt.into_option() {
+                    $hash_map.insert($key, val.unpack());
+                }
+            };
+        }
+
+        let mut map: HashMap<&'static str, f64> = HashMap::new();
+
+        // Profile indexes.
+        check_and_add!(anal.showalter, SHOWALTER, map);
+        check_and_add!(anal.swet, SWET, map);
+        check_and_add!(anal.k_index, K_INDEX, map);
+        check_and_add!(anal.lifted_index, LIFTED_INDEX, map);
+        check_and_add!(anal.lcl_pressure, LCL_PRESSURE, map);
+        check_and_add!(anal.pwat, PWAT, map);
+        check_and_add!(anal.total_totals, TOTAL_TOTALS, map);
+        check_and_add!(anal.cape, CAPE, map);
+        check_and_add!(anal.cin, CIN, map);
+        check_and_add!(anal.lcl_temperature, LCL_TEMPERATURE, map);
+        check_and_add!(anal.equilibrium_level, EQUILIBRIUM_LEVEL, map);
+        check_and_add!(anal.lfc, LFC, map);
+        check_and_add!(anal.bulk_richardson_number, BULK_RICHARDSON_NUMBER, map);
+
+        // Surface data.
+        check_and_add!(anal.skin_temperature, SKIN_TEMPERATURE, map);
+        check_and_add!(anal.layer_1_soil_temperature, LAYER_1_SOIL_TEMPERATURE, map);
+        check_and_add!(anal.snow_1hr, SNOW_1HR, map);
+        check_and_add!(anal.precip_1hr, PRECIP_1HR, map);
+        check_and_add!(anal.convective_precip_1hr, CONVECTIVE_PRECIP_1HR, map);
+        check_and_add!(anal.layer_2_soil_temperature, LAYER_2_SOIL_TEMPERATURE, map);
+        check_and_add!(anal.snow_ratio, SNOW_RATIO, map);
+        check_and_add!(anal.visibility, VISIBILITY, map);
+        check_and_add!(anal.storm_relative_helicity, STORM_RELATIVE_HELICITY, map);
+
+        if let Some(WindUV {
+            u: MetersPSec(u),
+            v: MetersPSec(v),
+        }) = anal.storm_motion.into_option()
+        {
+            map.insert(STORM_MOTION_U, u);
+            map.insert(STORM_MOTION_V, v);
+        }
+
+        if let Some(p_type) = anal.wx_symbol_code {
+            map.insert(WX_SYMBOL_CODE, p_type as u8 as f64);
+        }
+
+        map
+    }
+}
@@ -1,19 +1,47 @@
 //! Module for reading a bufkit file and breaking it into smaller pieces for parsing later.
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
+use std::ops::Range;
 use std::path::Path;
 
+#[cfg(feature = "cache")]
+mod cache;
 mod combine;
+mod export;
+mod merge;
+mod owned;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod sanity;
 mod surface;
 mod surface_section;
 mod upper_air;
 mod upper_air_section;
+mod write;
 
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use metfor::{Mm, Quantity};
 use sounding_analysis::Sounding;
 
-use self::surface_section::{SurfaceIterator, SurfaceSection};
-use self::upper_air_section::{UpperAirIterator, UpperAirSection};
+pub use self::merge::{load_directory, merge_soundings};
+pub use self::owned::OwnedSoundings;
+pub use self::sanity::Warning;
+pub use self::surface::{SfcColName, SfcColumns, SurfaceData};
+pub use self::surface_section::{SurfaceIterator, SurfaceSection};
+use self::upper_air::Location;
+pub use self::upper_air::{Level, ProfileField, UpperAir, UpperAirBuilder};
+pub use self::upper_air_section::UpperAirIterator;
+use self::upper_air_section::UpperAirSection;
+use crate::analysis::ProviderAnalysis;
 use crate::error::*;
+use crate::model::Model;
+use crate::parse_util::ParseOptions;
+
+/// A `Sounding` along with a map of the indexes and other values provided by the model.
+pub(crate) type AnalyzedSounding = (Sounding, HashMap<&'static str, f64>);
+
+/// The name used for a file when it isn't known.
+const UNKNOWN_FILE_NAME: &str = "Unknown File";
 
 /// Hold an entire bufkit file in memory.
 pub struct BufkitFile {
@@ -33,13 +61,22 @@ impl BufkitFile {
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
 
-        Ok(BufkitFile {
-            file_text: contents,
-            file_name: path
-                .file_name()
+        Ok(BufkitFile::from_string(
+            contents,
+            path.file_name()
                 .map(|s| s.to_string_lossy().to_string())
-                .unwrap_or_else(|| "Unknown File".to_owned()),
-        })
+                .unwrap_or_else(|| UNKNOWN_FILE_NAME.to_owned()),
+        ))
+    }
+
+    /// Create a file from text that is already in memory, e.g. downloaded from the internet.
+    ///
+    /// The `file_name` is used as the source description of the soundings.
+    pub fn from_string(file_text: String, file_name: String) -> BufkitFile {
+        BufkitFile {
+            file_text,
+            file_name,
+        }
     }
 
     /// Validate the whole file, ensure it is parseable and do some sanity checks.
@@ -50,22 +87,95 @@ impl BufkitFile {
         Ok(())
     }
 
+    /// Validate the whole file like `validate_file_format`, keeping the parsed soundings.
+    ///
+    /// Validating and then iterating over `data` parses every sounding twice, this only parses
+    /// them once. The tradeoff is memory, every sounding in the file is held at once instead of
+    /// being parsed as needed, which is several times the size of the file text.
+    pub fn into_validated_data(self) -> Result<OwnedSoundings, Box<dyn Error>> {
+        let soundings = self.data()?.parse_validated()?;
+
+        Ok(OwnedSoundings::new(soundings, self.file_name))
+    }
+
+    /// Parse all of the soundings into a value that doesn't borrow the text of the file.
+    ///
+    /// This gives the same soundings as iterating over `data`, so soundings that fail to parse
+    /// are skipped. Use `into_validated_data` to fail on bad soundings instead.
+    pub fn into_soundings(self) -> Result<OwnedSoundings, Box<dyn Error>> {
+        let soundings = self.data()?.into_iter().collect();
+
+        Ok(OwnedSoundings::new(soundings, self.file_name))
+    }
+
     /// Get a bufkit data object from this file.
     pub fn data(&self) -> Result<BufkitData<'_>, Box<dyn Error>> {
         BufkitData::init(&self.file_text, &self.file_name)
     }
 
+    /// Get the latitude, longitude, and elevation of the station from the first sounding.
+    ///
+    /// Only the station info of the first sounding is parsed, so this is much cheaper than
+    /// iterating over the data.
+    pub fn station_location(&self) -> Result<Location, Box<dyn Error>> {
+        let start = self
+            .file_text
+            .find("STID =")
+            .ok_or_else(|| BufkitFileError::MissingSection("station info".to_owned()))?;
+
+        Ok(UpperAir::parse_location(&self.file_text[start..])?)
+    }
+
+    /// Guess which model produced the file from the file name.
+    pub fn model(&self) -> Model {
+        Model::from_file_name(&self.file_name)
+    }
+
     /// Get the raw string data from the file.
     pub fn raw_text(&self) -> &str {
         &self.file_text
     }
 }
 
+impl std::convert::TryFrom<BufkitFile> for OwnedSoundings {
+    type Error = Box<dyn Error>;
+
+    /// Parse all the soundings up front, like `into_soundings`.
+    ///
+    /// This makes `for snd in OwnedSoundings::try_from(file)? { ... }` work without keeping the
+    /// file alive. It fails if the file can't be split into upper air and surface sections.
+    fn try_from(file: BufkitFile) -> Result<Self, Self::Error> {
+        file.into_soundings()
+    }
+}
+
+impl std::str::FromStr for BufkitFile {
+    type Err = BufkitFileError;
+
+    /// Copy the text into a new `BufkitFile`, checking that it can be split into the upper air
+    /// and surface sections.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let break_point = BufkitData::find_break_point(text)?;
+        BufkitData::new_with_break_point(
+            text,
+            break_point,
+            UNKNOWN_FILE_NAME,
+            ParseOptions::default(),
+        )?;
+
+        Ok(BufkitFile::from_string(
+            text.to_owned(),
+            UNKNOWN_FILE_NAME.to_owned(),
+        ))
+    }
+}
+
 /// References to different data sections within a `BufkitFile` mainly useful for generating
 /// iterators.
 ///
 /// This is theoretically not necessary without lexical lifetimes.
 pub struct BufkitData<'a> {
+    text: &'a str,
     upper_air: UpperAirSection<'a>,
     surface: SurfaceSection<'a>,
     file_name: &'a str,
@@ -73,41 +183,404 @@ pub struct BufkitData<'a> {
 
 impl<'a> BufkitData<'a> {
     /// Validate the whole string, ensure it is parseable and do some sanity checks.
+    ///
+    /// Parsing errors have the line number in the file where they happened.
     pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        use crate::parse_util::line_of;
+
         self.upper_air.validate_section()?;
-        self.surface.validate_section()?;
+        self.surface
+            .validate_section()
+            .map_err(|err| err.located(line_of(self.text, self.surface.text())))?;
+        self.validate_times_overlap()?;
+        self.validate_station_nums()?;
         Ok(())
     }
 
+    /// Check that the upper air and surface data at each valid time have the same station number.
+    ///
+    /// A mismatch means the file is corrupt or two files were stuck together. The error has the
+    /// line in the file of the surface record that doesn't match. Data that fails to parse is
+    /// skipped, the section validation finds those.
+    fn validate_station_nums(&self) -> Result<(), BufkitFileError> {
+        use crate::parse_util::{check_missing_i32, line_of};
+
+        let mut ua_iter = self.upper_air.into_iter();
+        let mut sd_iter = self.surface.into_iter();
+        let mut next_ua = ua_iter.next_timed_chunk();
+        let mut next_sd = sd_iter.next_timed_chunk();
+
+        while let (Some((ua_time, ua_chunk)), Some((sd_time, sd_chunk))) = (next_ua, next_sd) {
+            if ua_time < sd_time {
+                next_ua = ua_iter.next_timed_chunk();
+            } else if sd_time < ua_time {
+                next_sd = sd_iter.next_timed_chunk();
+            } else {
+                let ua_num = UpperAir::parse_station_num(ua_chunk);
+                let sd_num = SurfaceData::parse_station_num(sd_chunk, self.surface.columns());
+                if let (Ok(expected), Ok(found)) = (ua_num, sd_num) {
+                    // A blank station number in the upper air section can't be checked.
+                    if check_missing_i32(expected).is_some() && expected != found {
+                        let err = BufkitFileError::StationMismatch { expected, found };
+                        return Err(err.located(line_of(self.text, sd_chunk.trim_start())));
+                    }
+                }
+                next_ua = ua_iter.next_timed_chunk();
+                next_sd = sd_iter.next_timed_chunk();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the upper air and surface sections have at least one valid time in common, if
+    /// they aren't empty.
+    fn validate_times_overlap(&self) -> Result<(), BufkitFileError> {
+        // How many times from each section to put in the error.
+        const NUM_TIMES: usize = 3;
+
+        if self.matched_times().next().is_some() {
+            return Ok(());
+        }
+
+        let upper_air: Vec<NaiveDateTime> = self.upper_air.valid_times().take(NUM_TIMES).collect();
+        let surface: Vec<NaiveDateTime> = self.surface.valid_times().take(NUM_TIMES).collect();
+        if upper_air.is_empty() || surface.is_empty() {
+            return Ok(());
+        }
+
+        Err(BufkitFileError::DisjointTimes { upper_air, surface })
+    }
+
+    /// Parse every sounding, failing on the first error exactly like `validate`.
+    ///
+    /// Each sounding is only parsed once, so this is cheaper than calling `validate` and then
+    /// iterating.
+    pub(crate) fn parse_validated(&self) -> Result<Vec<AnalyzedSounding>, BufkitFileError> {
+        use crate::parse_util::line_of;
+
+        let upper_air = self.upper_air.parse_all()?;
+        let surface = self
+            .surface
+            .parse_all()
+            .map_err(|err| err.located(line_of(self.text, self.surface.text())))?;
+        self.validate_times_overlap()?;
+        self.validate_station_nums()?;
+
+        let mut upper_air = upper_air.into_iter();
+        let mut surface = surface.into_iter();
+        Ok(
+            std::iter::from_fn(|| next_matching_pair(&mut upper_air, &mut surface))
+                .map(|(ua, sd)| combine::combine_data(ua, sd, self.file_name))
+                .collect(),
+        )
+    }
+
+    /// Validate the whole string like `validate`, but keep going after an error and collect all
+    /// of them.
+    ///
+    /// Each error is paired with the line in the file where the bad sounding or surface record
+    /// starts, not the index of the record, since an index alone can't say which section the
+    /// record is in. The error itself has the line number of the bad value, which may be a few
+    /// lines after the start of the record. Errors about the file as a whole, like the two
+    /// sections having no valid times in common, are paired with the first line of the surface
+    /// section. An empty vector means the data is valid.
+    pub fn validate_collect(&self) -> Vec<(usize, BufkitFileError)> {
+        use crate::parse_util::line_of;
+
+        let mut errors = self.upper_air.validate_collect();
+
+        let offset = line_of(self.text, self.surface.text());
+        errors.extend(
+            self.surface
+                .validate_collect()
+                .into_iter()
+                .map(|(line, err)| (offset + line - 1, err.located(offset))),
+        );
+
+        if let Err(err) = self.validate_times_overlap() {
+            errors.push((offset, err));
+        }
+        if let Err(err) = self.validate_station_nums() {
+            errors.push((err.line().unwrap_or(offset), err));
+        }
+
+        errors
+    }
+
     /// Initialize struct for parsing a sounding.
+    ///
+    /// A UTF-8 byte order mark at the start of `text` is ignored.
     pub fn init(text: &'a str, fname: &'a str) -> Result<BufkitData<'a>, Box<dyn Error>> {
-        let break_point = BufkitData::find_break_point(text)?;
-        let data = BufkitData::new_with_break_point(text, break_point, fname)?;
+        BufkitData::init_with_options(text, fname, ParseOptions::default())
+    }
+
+    /// Initialize struct for parsing a sounding, using `options` instead of the defaults.
+    ///
+    /// This is useful for non-standard files, e.g. ones that use `-999` for missing values.
+    pub fn init_with_options(
+        text: &'a str,
+        fname: &'a str,
+        options: ParseOptions,
+    ) -> Result<BufkitData<'a>, Box<dyn Error>> {
+        let stripped = text.strip_prefix('\u{FEFF}').unwrap_or(text);
+        let break_point = BufkitData::find_break_point(stripped)?;
+        let mut data = BufkitData::new_with_break_point(stripped, break_point, fname, options)?;
+        // Keep any byte order mark so byte offsets are relative to the original text.
+        data.text = text;
         Ok(data)
     }
 
+    /// Get the byte ranges of the upper air and surface sections in the text of the file, e.g.
+    /// `BufkitFile::raw_text`.
+    ///
+    /// The sections are split at the surface header, so together they cover the whole text except
+    /// for a leading byte order mark.
+    pub fn section_ranges(&self) -> (Range<usize>, Range<usize>) {
+        use crate::parse_util::range_of;
+
+        (
+            range_of(self.text, self.upper_air.text()),
+            range_of(self.text, self.surface.text()),
+        )
+    }
+
+    /// Count the number of soundings without parsing them.
+    ///
+    /// This still walks through all of the text, so it is O(n), but it only parses the valid
+    /// times of the upper air and surface sections, which is much cheaper than a full parse. A
+    /// sounding whose profile fails to parse is still counted, so this may be larger than the
+    /// number of items produced by iterating.
+    pub fn len(&self) -> usize {
+        self.matched_times().count()
+    }
+
+    /// Check if there are any soundings, without parsing them.
+    pub fn is_empty(&self) -> bool {
+        self.matched_times().next().is_none()
+    }
+
+    /// Get the initialization time of the model run, taken from the first sounding in the file.
+    ///
+    /// This is useful for grouping files by model cycle.
+    pub fn init_time(&self) -> Option<NaiveDateTime> {
+        self.upper_air
+            .into_iter()
+            .next()
+            .map(|upper_air| upper_air.init_time())
+    }
+
+    /// Get the valid times of all the soundings in the file, without parsing the profiles.
+    ///
+    /// These are the times that have both upper air and surface data, so any of them can be used
+    /// with `sounding_at`.
+    pub fn valid_times(&self) -> Vec<NaiveDateTime> {
+        self.matched_times().collect()
+    }
+
+    /// Get the time between soundings, without parsing the profiles.
+    ///
+    /// If the spacing is irregular, e.g. hourly output that switches to 3-hourly later in the
+    /// forecast, this is the most common spacing. Ties go to the shorter spacing. Returns `None`
+    /// if there are fewer than two soundings.
+    pub fn time_step(&self) -> Option<Duration> {
+        let mut counts: BTreeMap<Duration, usize> = BTreeMap::new();
+
+        let times = self.valid_times();
+        for pair in times.windows(2) {
+            *counts.entry(pair[1] - pair[0]).or_insert(0) += 1;
+        }
+
+        // Iterate in reverse so the shortest spacing wins ties.
+        counts
+            .into_iter()
+            .rev()
+            .max_by_key(|&(_, count)| count)
+            .map(|(step, _)| step)
+    }
+
+    /// Get all the soundings grouped by the date of their valid time.
+    ///
+    /// Within each day the soundings are in the same order as the file.
+    pub fn soundings_by_day(&self) -> BTreeMap<NaiveDate, Vec<AnalyzedSounding>> {
+        let mut days: BTreeMap<NaiveDate, Vec<AnalyzedSounding>> = BTreeMap::new();
+
+        for (snd, anal) in self {
+            if let Some(valid_time) = snd.valid_time() {
+                days.entry(valid_time.date()).or_default().push((snd, anal));
+            }
+        }
+
+        days
+    }
+
+    /// Sum the 1-hour precipitation (P01M) in the surface data over each day.
+    ///
+    /// Each hourly value is assigned to the date of its valid time, so the precipitation for the
+    /// hour ending at 00Z is counted on the new day. If `missing_as_zero` is `true`, missing values
+    /// count as no precipitation. Otherwise a day with any missing values is left out of the map,
+    /// since its total would be incomplete. Files without a P01M column have all values missing.
+    pub fn daily_precip_totals(&self, missing_as_zero: bool) -> BTreeMap<NaiveDate, Mm> {
+        let mut days: BTreeMap<NaiveDate, Option<f64>> = BTreeMap::new();
+
+        for sd in &self.surface {
+            let total = days.entry(sd.valid_time.date()).or_insert(Some(0.0));
+
+            match sd.p01.into_option() {
+                Some(pcp) => *total = total.map(|total| total + pcp.unpack()),
+                None if missing_as_zero => {}
+                None => *total = None,
+            }
+        }
+
+        days.into_iter()
+            .filter_map(|(day, total)| total.map(|total| (day, Mm(total))))
+            .collect()
+    }
+
+    /// Iterate over the soundings with a valid time from `start` to `end`, inclusive.
+    ///
+    /// Soundings in a file are in order of increasing valid time, so iteration stops at the first
+    /// sounding after `end`.
+    pub fn soundings_between(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> impl Iterator<Item = AnalyzedSounding> + '_ {
+        self.into_iter()
+            .skip_while(move |(snd, _)| snd.valid_time().is_none_or(|vt| vt < start))
+            .take_while(move |(snd, _)| snd.valid_time().is_some_and(|vt| vt <= end))
+    }
+
+    /// Iterate over the soundings with a forecast lead time, in hours, that satisfies `pred`.
+    ///
+    /// For example, `data.soundings_by_lead(|lt| lt % 6 == 0)` keeps every 6th hour. Soundings
+    /// without a lead time are skipped.
+    pub fn soundings_by_lead<F>(&self, pred: F) -> impl Iterator<Item = AnalyzedSounding> + '_
+    where
+        F: Fn(i32) -> bool + 'a,
+    {
+        self.into_iter()
+            .filter(move |(snd, _)| snd.lead_time().into_option().is_some_and(&pred))
+    }
+
+    /// Iterate over the soundings with the values provided by the model in a `ProviderAnalysis`
+    /// instead of a `HashMap`.
+    pub fn analyses_typed(&self) -> impl Iterator<Item = (Sounding, ProviderAnalysis)> + '_ {
+        let mut iter = self.into_iter();
+
+        std::iter::from_fn(move || {
+            let (ua, sd) = iter.next_pair()?;
+            Some(combine::combine_data_typed(ua, sd, self.file_name))
+        })
+    }
+
+    /// Iterate over the soundings paired with their valid times, e.g. to collect them into a map
+    /// keyed by time.
+    ///
+    /// The valid time comes from the upper air data, so the `Sounding` doesn't need to be asked
+    /// for it. The values provided by the model are left out.
+    pub fn timed_soundings(&self) -> impl Iterator<Item = (NaiveDateTime, Sounding)> + '_ {
+        let mut iter = self.into_iter();
+
+        std::iter::from_fn(move || {
+            let (ua, sd) = iter.next_pair()?;
+            let valid_time = ua.valid_time;
+            let (snd, _) = combine::combine_data(ua, sd, self.file_name);
+            Some((valid_time, snd))
+        })
+    }
+
+    /// Iterate over just the surface data, without parsing the upper air section.
+    pub fn surface_data(&self) -> SurfaceIterator<'_> {
+        self.surface.into_iter()
+    }
+
+    /// Get the columns of the surface section, to see which ones the parser understood.
+    pub fn surface_columns(&self) -> &SfcColumns {
+        self.surface.columns()
+    }
+
+    /// Iterate over just the upper air soundings, without parsing the surface section or
+    /// matching up valid times.
+    pub fn upper_air(&self) -> UpperAirIterator<'_> {
+        self.upper_air.into_iter()
+    }
+
+    /// Get the first sounding in the file.
+    ///
+    /// This stops at the first upper air and surface data with matching valid times, so only the
+    /// first few records are parsed. Returns `None` if there are no soundings.
+    pub fn first_sounding(&self) -> Option<AnalyzedSounding> {
+        self.into_iter().next()
+    }
+
+    /// Get the last sounding in the file.
+    ///
+    /// This searches from the end of each section, so only the last few records are parsed.
+    /// Returns `None` if there are no soundings.
+    pub fn last_sounding(&self) -> Option<AnalyzedSounding> {
+        self.into_iter().next_back()
+    }
+
+    /// Get the sounding with the given valid time.
+    ///
+    /// Only the valid times are parsed while searching, so this is much cheaper than iterating
+    /// over all of the soundings. Returns `None` if either section has no data for that time.
+    pub fn sounding_at(&self, valid_time: NaiveDateTime) -> Option<AnalyzedSounding> {
+        let ua = self.upper_air.parse_at(valid_time)?;
+        let sd = self.surface.parse_at(valid_time)?;
+
+        Some(combine::combine_data(ua, sd, self.file_name))
+    }
+
+    /// Iterate over the valid times present in both the upper air and surface sections.
+    fn matched_times(&self) -> impl Iterator<Item = NaiveDateTime> + '_ {
+        let mut ua_times = self.upper_air.valid_times();
+        let mut sfc_times = self.surface.valid_times();
+
+        std::iter::from_fn(move || {
+            let mut next_ua = ua_times.next()?;
+            let mut next_sd = sfc_times.next()?;
+
+            loop {
+                while next_sd < next_ua {
+                    next_sd = sfc_times.next()?;
+                }
+                while next_ua < next_sd {
+                    next_ua = ua_times.next()?;
+                }
+                if next_ua == next_sd {
+                    return Some(next_ua);
+                }
+            }
+        })
+    }
+
     fn new_with_break_point(
         text: &'a str,
         break_point: usize,
         fname: &'a str,
+        options: ParseOptions,
     ) -> Result<BufkitData<'a>, BufkitFileError> {
         Ok(BufkitData {
-            upper_air: UpperAirSection::new(&text[0..break_point]),
-            surface: SurfaceSection::init(&text[break_point..])?,
+            text,
+            upper_air: UpperAirSection::with_options(&text[0..break_point], options.clone()),
+            surface: SurfaceSection::with_options(&text[break_point..], options)?,
             file_name: fname,
         })
     }
 
     fn find_break_point(text: &str) -> Result<usize, BufkitFileError> {
         match text.find("STN YYMMDD/HHMM") {
-            None => Err(BufkitFileError::new()),
+            None => Err(BufkitFileError::MissingBreakPoint),
             Some(val) => Ok(val),
         }
     }
 }
 
 impl<'a> IntoIterator for &'a BufkitData<'a> {
-    type Item = (Sounding, HashMap<&'static str, f64>);
+    type Item = AnalyzedSounding;
     type IntoIter = SoundingIterator<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
@@ -120,29 +593,112 @@ impl<'a> IntoIterator for &'a BufkitData<'a> {
 }
 
 /// Iterator type for `BufkitData` that returns a `Sounding`.
+///
+/// Profiles in the file where every value is missing are left empty in the returned `Sounding`.
 pub struct SoundingIterator<'a> {
     upper_air_it: UpperAirIterator<'a>,
     surface_it: SurfaceIterator<'a>,
     source_name: &'a str,
 }
 
-impl<'a> Iterator for SoundingIterator<'a> {
-    type Item = (Sounding, HashMap<&'static str, f64>);
+impl<'a> SoundingIterator<'a> {
+    /// Get the next pair of upper air and surface data with matching valid times.
+    ///
+    /// Only the valid times are parsed until a match is found, so the upper air and surface data
+    /// without a match are never fully parsed.
+    fn next_pair(&mut self) -> Option<(UpperAir, SurfaceData)> {
+        let (mut ua_time, mut ua_chunk) = self.upper_air_it.next_timed_chunk()?;
+        let (mut sd_time, mut sd_chunk) = self.surface_it.next_timed_chunk()?;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let mut next_ua = self.upper_air_it.next()?;
-        let mut next_sd = self.surface_it.next()?;
+        loop {
+            while sd_time < ua_time {
+                (sd_time, sd_chunk) = self.surface_it.next_timed_chunk()?;
+            }
+            while ua_time < sd_time {
+                (ua_time, ua_chunk) = self.upper_air_it.next_timed_chunk()?;
+            }
+            if ua_time == sd_time {
+                // If either one fails to parse, move past it and look for the next match.
+                match self.upper_air_it.parse_chunk(ua_chunk) {
+                    Some(ua) => match self.surface_it.parse_chunk(sd_chunk) {
+                        Some(sd) => return Some((ua, sd)),
+                        None => (sd_time, sd_chunk) = self.surface_it.next_timed_chunk()?,
+                    },
+                    None => (ua_time, ua_chunk) = self.upper_air_it.next_timed_chunk()?,
+                }
+            }
+        }
+    }
+
+    /// Get the next pair of upper air and surface data with matching valid times, starting from
+    /// the end.
+    fn next_back_pair(&mut self) -> Option<(UpperAir, SurfaceData)> {
+        let mut next_ua = self.upper_air_it.next_back()?;
+        let mut next_sd = self.surface_it.next_back()?;
 
         loop {
-            while next_sd.valid_time < next_ua.valid_time {
-                next_sd = self.surface_it.next()?;
+            while next_sd.valid_time > next_ua.valid_time {
+                next_sd = self.surface_it.next_back()?;
             }
-            while next_ua.valid_time < next_sd.valid_time {
-                next_ua = self.upper_air_it.next()?;
+            while next_ua.valid_time > next_sd.valid_time {
+                next_ua = self.upper_air_it.next_back()?;
             }
             if next_ua.valid_time == next_sd.valid_time {
-                return Some(combine::combine_data(next_ua, next_sd, &self.source_name));
+                return Some((next_ua, next_sd));
             }
         }
     }
 }
+
+/// Get the next pair of upper air and surface data with matching valid times, skipping any
+/// without a match.
+fn next_matching_pair<UA, SD>(
+    upper_air: &mut UA,
+    surface: &mut SD,
+) -> Option<(UpperAir, SurfaceData)>
+where
+    UA: Iterator<Item = UpperAir>,
+    SD: Iterator<Item = SurfaceData>,
+{
+    let mut next_ua = upper_air.next()?;
+    let mut next_sd = surface.next()?;
+
+    loop {
+        while next_sd.valid_time < next_ua.valid_time {
+            next_sd = surface.next()?;
+        }
+        while next_ua.valid_time < next_sd.valid_time {
+            next_ua = upper_air.next()?;
+        }
+        if next_ua.valid_time == next_sd.valid_time {
+            return Some((next_ua, next_sd));
+        }
+    }
+}
+
+impl<'a> Iterator for SoundingIterator<'a> {
+    type Item = AnalyzedSounding;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (ua, sd) = self.next_pair()?;
+        Some(combine::combine_data(ua, sd, self.source_name))
+    }
+
+    /// Skip `n` soundings without combining them into a `Sounding`.
+    ///
+    /// The skipped soundings are still parsed so the ones that fail to parse are skipped the same
+    /// way as with `next`.
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        for _ in 0..n {
+            self.next_pair()?;
+        }
+        self.next()
+    }
+}
+
+impl<'a> DoubleEndedIterator for SoundingIterator<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (ua, sd) = self.next_back_pair()?;
+        Some(combine::combine_data(ua, sd, self.source_name))
+    }
+}
@@ -0,0 +1,90 @@
+//! Read and write a compact binary cache of the parsed data in a bufkit file.
+use std::error::Error;
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use super::combine;
+use super::surface::SurfaceData;
+use super::upper_air::UpperAir;
+use super::{AnalyzedSounding, BufkitData};
+use crate::error::*;
+
+/// Written at the start of every cache so other files are quickly rejected.
+const CACHE_MAGIC: &[u8; 8] = b"BUFKITCA";
+
+/// Increment this any time the layout or units of `UpperAir` or `SurfaceData` change so stale
+/// caches are rejected instead of being decoded into garbage.
+const CACHE_VERSION: u32 = 4;
+
+#[derive(Serialize, Deserialize)]
+struct Cache {
+    source_name: String,
+    data: Vec<(UpperAir, SurfaceData)>,
+}
+
+impl<'a> BufkitData<'a> {
+    /// Parse all the soundings and write them to a compact binary cache.
+    ///
+    /// Loading the cache with `read_cache` is much faster than parsing the text again, which is
+    /// useful when repeatedly analyzing the same set of files.
+    pub fn write_cache<W: Write>(&self, mut writer: W) -> Result<(), Box<dyn Error>> {
+        let mut iter = self.into_iter();
+        let mut data = vec![];
+        while let Some(pair) = iter.next_pair() {
+            data.push(pair);
+        }
+
+        let cache = Cache {
+            source_name: self.file_name.to_owned(),
+            data,
+        };
+
+        writer.write_all(CACHE_MAGIC)?;
+        writer.write_all(&CACHE_VERSION.to_le_bytes())?;
+        bincode::serialize_into(writer, &cache)?;
+
+        Ok(())
+    }
+
+    /// Read the soundings back from a cache created with `write_cache`.
+    ///
+    /// Returns an error if the cache was created with an incompatible version of this crate.
+    pub fn read_cache<R: Read>(mut reader: R) -> Result<Vec<AnalyzedSounding>, Box<dyn Error>> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != CACHE_MAGIC {
+            return Err(BufkitFileError::InvalidCache.into());
+        }
+
+        let mut version = [0u8; 4];
+        reader.read_exact(&mut version)?;
+        if u32::from_le_bytes(version) != CACHE_VERSION {
+            return Err(BufkitFileError::InvalidCache.into());
+        }
+
+        let cache: Cache = bincode::deserialize_from(reader)?;
+        let source_name = cache.source_name;
+
+        Ok(cache
+            .data
+            .into_iter()
+            .map(|(ua, sd)| combine::combine_data(ua, sd, &source_name))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stale_cache_rejected() {
+        let mut buf: Vec<u8> = CACHE_MAGIC.to_vec();
+        buf.extend_from_slice(&(CACHE_VERSION + 1).to_le_bytes());
+        assert!(BufkitData::read_cache(buf.as_slice()).is_err());
+
+        let buf: Vec<u8> = b"NOTACACHE\x01\x00\x00\x00".to_vec();
+        assert!(BufkitData::read_cache(buf.as_slice()).is_err());
+    }
+}
@@ -1,25 +1,42 @@
 //! Module for reading a bufkit file and breaking it into smaller pieces for parsing later.
 use super::surface::SurfaceData;
 use super::upper_air::UpperAir;
-use crate::parse_util::check_missing_i32;
-use metfor::{MetersPSec, Quantity, WindUV};
-use optional::Optioned;
-use sounding_analysis::{PrecipType, Sounding, StationInfo};
+use super::AnalyzedSounding;
+use crate::analysis::ProviderAnalysis;
+use crate::parse_util::{check_missing_i32, is_all_missing};
+use optional::{Noned, Optioned};
+use sounding_analysis::{Sounding, StationInfo};
 use std::collections::HashMap;
 
+/// Combine an upper air section and a surface section into a `Sounding` and a map of the indexes
+/// and other values provided by the model that are not part of a `Sounding`.
+pub fn combine_data(ua: UpperAir, sd: SurfaceData, fname: &str) -> AnalyzedSounding {
+    let (snd, anal) = combine_data_typed(ua, sd, fname);
+    (snd, HashMap::from(&anal))
+}
+
+/// Combine an upper air section and a surface section into a `Sounding` and the indexes and
+/// other values provided by the model that are not part of a `Sounding`.
+///
+/// The cloud fraction is stored as a fraction in `UpperAir`, but a `Sounding` expects percent,
+/// so it is converted back. If every value in the cloud fraction profile is missing, it is not
+/// attached to the `Sounding`, so it looks the same as a file without a CFRL column.
 #[allow(clippy::needless_pass_by_value)]
-pub fn combine_data(
+pub fn combine_data_typed(
     ua: UpperAir,
     sd: SurfaceData,
     fname: &str,
-) -> (Sounding, HashMap<&'static str, f64>) {
+) -> (Sounding, ProviderAnalysis) {
     let coords: Option<(f64, f64)> = ua
         .lat
         .into_option()
         .and_then(|lat| ua.lon.into_option().map(|lon| (lat, lon)));
 
-    let station =
-        StationInfo::new_with_values(check_missing_i32(ua.num), ua.id, coords, ua.elevation);
+    let cloud_fraction = drop_if_all_missing(ua.cloud_fraction_percent());
+
+    // The station info in a Sounding needs its own copy of the id.
+    let id = ua.id.map(|id| id.to_string());
+    let station = StationInfo::new_with_values(check_missing_i32(ua.num), id, coords, ua.elevation);
 
     let snd = Sounding::new()
         .with_source_description(fname.to_owned())
@@ -35,7 +52,7 @@ pub fn combine_data(
         .with_wind_profile(ua.wind)
         .with_pvv_profile(ua.omega)
         .with_height_profile(ua.height)
-        .with_cloud_fraction_profile(ua.cloud_fraction)
+        .with_cloud_fraction_profile(cloud_fraction)
         // Surface data
         .with_mslp(sd.mslp)
         .with_sfc_temperature(sd.temperature)
@@ -46,114 +63,84 @@ pub fn combine_data(
         .with_high_cloud(sd.hi_cloud)
         .with_sfc_wind(sd.wind);
 
-    macro_rules! check_and_add {
-        ($opt:expr, $key:expr, $hash_map:ident) => {
-            if let Some(val) = $opt.into_option() {
-                $hash_map.insert($key, val.unpack());
-            }
-        };
-    }
-
-    let mut bufkit_anal: HashMap<&'static str, f64> = HashMap::new();
-
-    // Add some profile indexes.
-    check_and_add!(ua.show, "Showalter", bufkit_anal);
-    check_and_add!(ua.swet, "SWeT", bufkit_anal);
-    check_and_add!(ua.kinx, "K", bufkit_anal);
-    check_and_add!(ua.li, "LI", bufkit_anal);
-    check_and_add!(ua.lclp, "LCL", bufkit_anal);
-    check_and_add!(ua.pwat, "PWAT", bufkit_anal);
-    check_and_add!(ua.totl, "TotalTotals", bufkit_anal);
-    check_and_add!(ua.cape, "CAPE", bufkit_anal);
-    check_and_add!(ua.cins, "CIN", bufkit_anal);
-    check_and_add!(ua.lclt, "LCLTemperature", bufkit_anal);
-    check_and_add!(ua.eqlv, "EquilibriumLevel", bufkit_anal);
-    check_and_add!(ua.lfc, "LFC", bufkit_anal);
-    check_and_add!(ua.brch, "BulkRichardsonNumber", bufkit_anal);
-
-    // Add some surface data
-    check_and_add!(sd.skin_temp, "SkinTemperature", bufkit_anal);
-    check_and_add!(sd.lyr_1_soil_temp, "Layer1SoilTemp", bufkit_anal);
-    check_and_add!(sd.snow_1hr, "SnowFall1HourKgPerMeterSquared", bufkit_anal);
-    check_and_add!(sd.p01, "Precipitation1HrMm", bufkit_anal);
-    check_and_add!(sd.c01, "ConvectivePrecip1HrMm", bufkit_anal);
-    check_and_add!(sd.lyr_2_soil_temp, "Layer2SoilTemp", bufkit_anal);
-    check_and_add!(sd.snow_ratio, "SnowRatio", bufkit_anal);
-    check_and_add!(sd.visibility, "VisibilityKm", bufkit_anal);
-    check_and_add!(sd.srh, "StormRelativeHelicity", bufkit_anal);
-
-    if let Some(WindUV {
-        u: MetersPSec(u),
-        v: MetersPSec(v),
-    }) = sd.storm_motion.into_option()
-    {
-        bufkit_anal.insert("StormMotionUMps", u);
-        bufkit_anal.insert("StormMotionVMps", v);
-    }
-
     // Get the Wx symbol code from bufkit and translate it into the kind that is used in
     // sounding-analysis.
-    let wx_code: Optioned<f64> = derived_wx_code(
-        sd.wx_sym_cod.map(|code| code as u8),
-        sd.rain_type,
-        sd.snow_type,
-        sd.fzra_type,
-        sd.ice_pellets_type,
-    )
-    .map(|p_type| p_type as u8 as f64)
-    .into();
-    check_and_add!(wx_code, "WxSymbolCode", bufkit_anal);
+    let wx_symbol_code = sd.precip_type();
+
+    let bufkit_anal = ProviderAnalysis {
+        // Profile indexes.
+        showalter: ua.show,
+        swet: ua.swet,
+        k_index: ua.kinx,
+        lifted_index: ua.li,
+        lcl_pressure: ua.lclp,
+        pwat: ua.pwat,
+        total_totals: ua.totl,
+        cape: ua.cape,
+        cin: ua.cins,
+        lcl_temperature: ua.lclt,
+        equilibrium_level: ua.eqlv,
+        lfc: ua.lfc,
+        bulk_richardson_number: ua.brch,
+
+        // Surface data.
+        skin_temperature: sd.skin_temp,
+        layer_1_soil_temperature: sd.lyr_1_soil_temp,
+        snow_1hr: sd.snow_1hr,
+        precip_1hr: sd.p01,
+        convective_precip_1hr: sd.c01,
+        layer_2_soil_temperature: sd.lyr_2_soil_temp,
+        snow_ratio: sd.snow_ratio,
+        visibility: sd.visibility,
+        storm_relative_helicity: sd.srh,
+        storm_motion: sd.storm_motion,
+        wx_symbol_code,
+    };
 
     (snd, bufkit_anal)
 }
-fn derived_wx_code(
-    wx_code: Option<u8>,
-    is_rain: Option<bool>,
-    is_snow: Option<bool>,
-    is_fzra: Option<bool>,
-    is_ip: Option<bool>,
-) -> Option<PrecipType> {
-    match wx_code {
-        Some(60) => Some(PrecipType::LightRain),
-        Some(66) => Some(PrecipType::LightFreezingRain),
-        Some(70) => Some(PrecipType::LightSnow),
-        Some(79) => Some(PrecipType::LightIcePellets),
-        _ => return None,
+
+/// Replace a profile with an empty one if it has no valid values.
+fn drop_if_all_missing<T: Noned + Copy>(profile: Vec<Optioned<T>>) -> Vec<Optioned<T>> {
+    if is_all_missing(&profile) {
+        vec![]
+    } else {
+        profile
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_all_missing_cloud_fraction_not_attached() {
+        let test_data = "STID = KMSO STNM = 727730 TIME = 170401/0100
+                         SLAT = 46.87 SLON = -114.16 SELV = 1335.0
+                         STIM = 1
+
+                         SHOW = 8.12 LIFT = 8.00 SWET = 39.08 KINX = 14.88
+                         LCLP = 780.77 PWAT = 9.28 TOTL = 39.55 CAPE = 0.00
+                         LCLT = 272.88 CINS = 0.00 EQLV = -9999.00 LFCT = -9999.00
+                         BRCH = 0.00
+
+                         PRES TMPC TMWC DWPC THTE DRCT SKNT OMEG
+                         CFRL HGHT
+                         867.20 8.04 4.71 1.19 307.17 288.43 2.45 0.00
+                         -9999.00 1353.07
+                         863.50 7.64 4.42 0.99 306.96 293.63 3.40 0.00
+                         -9999.00 1388.34
+                         859.80 7.24 4.18 0.90 306.87 292.38 3.57 0.00
+                         -9999.00 1423.71";
+
+        let ua = UpperAir::parse(test_data).unwrap();
+        assert_eq!(ua.cloud_fraction.len(), 3);
+        assert!(is_all_missing(&ua.cloud_fraction));
+
+        let (snd, _) = combine_data(ua, SurfaceData::default(), "test");
+        assert!(snd.cloud_fraction_profile().is_empty());
+        // The surface level is added to the front of the profiles.
+        assert_eq!(snd.pressure_profile().len(), 4);
+        assert_eq!(snd.temperature_profile().len(), 4);
     }
-    .or_else(|| {
-        is_rain.and_then(|isra| {
-            if isra {
-                Some(PrecipType::LightRain)
-            } else {
-                None
-            }
-        })
-    })
-    .or_else(|| {
-        is_snow.and_then(|issn| {
-            if issn {
-                Some(PrecipType::LightSnow)
-            } else {
-                None
-            }
-        })
-    })
-    .or_else(|| {
-        is_fzra.and_then(|isfz| {
-            if isfz {
-                Some(PrecipType::LightFreezingRain)
-            } else {
-                None
-            }
-        })
-    })
-    .or_else(|| {
-        is_ip.and_then(|isip| {
-            if isip {
-                Some(PrecipType::LightIcePellets)
-            } else {
-                None
-            }
-        })
-    })
 }
@@ -0,0 +1,125 @@
+//! Write the data in a bufkit file out in other formats.
+
+use super::BufkitData;
+use metfor::Quantity;
+use optional::{Noned, Optioned};
+use std::error::Error;
+use std::io::Write;
+
+#[cfg(feature = "serde")]
+use chrono::NaiveDateTime;
+#[cfg(feature = "serde")]
+use metfor::{Celsius, HectoPascal, Kelvin, Knots, Meters, PaPS, WindSpdDir};
+#[cfg(feature = "serde")]
+use sounding_analysis::Sounding;
+#[cfg(feature = "serde")]
+use std::collections::HashMap;
+
+const SURFACE_CSV_HEADER: &str = "valid_time,mslp_hpa,station_pres_hpa,temperature_c,\
+                                  dewpoint_c,wind_dir_deg,wind_spd_kt,low_cloud,mid_cloud,\
+                                  high_cloud,precip_1hr_mm";
+
+impl<'a> BufkitData<'a> {
+    /// Write the surface data as CSV, one row per valid time.
+    ///
+    /// The first row is a header with the column names and units. Missing values are left as
+    /// empty cells.
+    pub fn surface_to_csv<W: Write>(&self, mut w: W) -> Result<(), Box<dyn Error>> {
+        writeln!(w, "{}", SURFACE_CSV_HEADER)?;
+
+        for sd in &self.surface {
+            let wind = sd.wind.into_option();
+
+            writeln!(
+                w,
+                "{},{},{},{},{},{},{},{},{},{},{}",
+                sd.valid_time.format("%Y-%m-%d %H:%M"),
+                csv_cell(sd.mslp),
+                csv_cell(sd.station_pres),
+                csv_cell(sd.temperature),
+                csv_cell(sd.dewpoint),
+                wind.map(|w| format!("{:.0}", w.direction))
+                    .unwrap_or_default(),
+                wind.map(|w| format!("{:.1}", w.speed.unpack()))
+                    .unwrap_or_default(),
+                csv_cell(sd.low_cloud),
+                csv_cell(sd.mid_cloud),
+                csv_cell(sd.hi_cloud),
+                csv_cell(sd.p01),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the soundings as JSON lines, one JSON object per sounding on each line.
+    ///
+    /// Each object has the valid time, lead time, station information, profiles, and the
+    /// values provided by the model with the same keys as the `HashMap` from iterating.
+    #[cfg(feature = "serde")]
+    pub fn write_jsonl<W: Write>(&self, mut w: W) -> Result<(), Box<dyn Error>> {
+        for (snd, anal) in self {
+            serde_json::to_writer(&mut w, &JsonSounding::new(&snd, &anal))?;
+            writeln!(w)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The layout of a sounding in a JSON line.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct JsonSounding<'a> {
+    source: Option<&'a str>,
+    valid_time: Option<NaiveDateTime>,
+    lead_time: Optioned<i32>,
+    station_num: Optioned<i32>,
+    station_id: Option<&'a str>,
+    location: Option<(f64, f64)>,
+    elevation: Optioned<Meters>,
+    pressure: &'a [Optioned<HectoPascal>],
+    temperature: &'a [Optioned<Celsius>],
+    wet_bulb: &'a [Optioned<Celsius>],
+    dew_point: &'a [Optioned<Celsius>],
+    theta_e: &'a [Optioned<Kelvin>],
+    wind: &'a [Optioned<WindSpdDir<Knots>>],
+    pvv: &'a [Optioned<PaPS>],
+    height: &'a [Optioned<Meters>],
+    cloud_fraction: &'a [Optioned<f64>],
+    analysis: &'a HashMap<&'static str, f64>,
+}
+
+#[cfg(feature = "serde")]
+impl<'a> JsonSounding<'a> {
+    fn new(snd: &'a Sounding, anal: &'a HashMap<&'static str, f64>) -> Self {
+        let station = snd.station_info();
+
+        JsonSounding {
+            source: snd.source_description(),
+            valid_time: snd.valid_time(),
+            lead_time: snd.lead_time(),
+            station_num: station.station_num(),
+            station_id: station.station_id(),
+            location: station.location(),
+            elevation: station.elevation(),
+            pressure: snd.pressure_profile(),
+            temperature: snd.temperature_profile(),
+            wet_bulb: snd.wet_bulb_profile(),
+            dew_point: snd.dew_point_profile(),
+            theta_e: snd.theta_e_profile(),
+            wind: snd.wind_profile(),
+            pvv: snd.pvv_profile(),
+            height: snd.height_profile(),
+            cloud_fraction: snd.cloud_fraction_profile(),
+            analysis: anal,
+        }
+    }
+}
+
+/// Format a value for a CSV cell, missing values are empty.
+fn csv_cell<T: Quantity + Noned>(val: Optioned<T>) -> String {
+    val.into_option()
+        .map(|val| val.unpack().to_string())
+        .unwrap_or_default()
+}
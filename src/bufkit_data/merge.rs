@@ -0,0 +1,81 @@
+//! Combine the soundings from several files into one time series.
+
+use super::{AnalyzedSounding, BufkitData, BufkitFile};
+use crate::error::BufkitFileError;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+
+/// Merge the soundings from several files for the same station into one time series, sorted by
+/// valid time.
+///
+/// Where the files overlap, the sounding from the model run with the latest initialization time
+/// is kept. If two files are from the same run, the one later in `files` wins. Files without any
+/// soundings are ignored.
+///
+/// Returns an error if the files are not all for the same station number.
+pub fn merge_soundings(files: &[BufkitData<'_>]) -> Result<Vec<AnalyzedSounding>, BufkitFileError> {
+    let mut station_num = None;
+    let mut runs = Vec::with_capacity(files.len());
+
+    for data in files {
+        let first = match data.upper_air().next() {
+            Some(first) => first,
+            None => continue,
+        };
+
+        match station_num {
+            Some(expected) if expected != first.num => {
+                return Err(BufkitFileError::StationMismatch {
+                    expected,
+                    found: first.num,
+                });
+            }
+            _ => station_num = Some(first.num),
+        }
+
+        runs.push((first.init_time(), data));
+    }
+
+    // The sort is stable, so files from the same run stay in the order they were given.
+    runs.sort_by_key(|&(init_time, _)| init_time);
+
+    let mut merged = BTreeMap::new();
+    for (_, data) in runs {
+        for (snd, anal) in data {
+            if let Some(valid_time) = snd.valid_time() {
+                merged.insert(valid_time, (snd, anal));
+            }
+        }
+    }
+
+    Ok(merged.into_values().collect())
+}
+
+/// Load every bufkit file in a directory and merge them into one time series with
+/// `merge_soundings`.
+///
+/// Only files with a `.buf` extension are loaded, and sub-directories are not searched. Returns an
+/// error if a file fails to load or the files are for different stations.
+pub fn load_directory(dir: &Path) -> Result<Vec<AnalyzedSounding>, Box<dyn Error>> {
+    let mut paths = vec![];
+    for entry in dir.read_dir()? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "buf") {
+            paths.push(path);
+        }
+    }
+    // Sort so files from the same model run are merged in the same order every time.
+    paths.sort();
+
+    let files = paths
+        .iter()
+        .map(|path| BufkitFile::load(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let data = files
+        .iter()
+        .map(BufkitFile::data)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(merge_soundings(&data)?)
+}
@@ -0,0 +1,62 @@
+//! Parsed soundings that do not borrow the text of the file they came from.
+
+use super::AnalyzedSounding;
+use std::slice::Iter;
+use std::vec::IntoIter;
+
+/// All the soundings from a bufkit file, parsed up front.
+///
+/// Unlike `BufkitData`, this owns all of its data, so it can outlive the `BufkitFile` it came
+/// from.
+#[derive(Debug, Clone)]
+pub struct OwnedSoundings {
+    soundings: Vec<AnalyzedSounding>,
+    file_name: String,
+}
+
+impl OwnedSoundings {
+    pub(crate) fn new(soundings: Vec<AnalyzedSounding>, file_name: String) -> Self {
+        OwnedSoundings {
+            soundings,
+            file_name,
+        }
+    }
+
+    /// The number of soundings.
+    pub fn len(&self) -> usize {
+        self.soundings.len()
+    }
+
+    /// Whether there are any soundings.
+    pub fn is_empty(&self) -> bool {
+        self.soundings.is_empty()
+    }
+
+    /// The name of the file the soundings came from.
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// Iterate over the soundings, in the same order as iterating over `BufkitData`.
+    pub fn iter(&self) -> Iter<'_, AnalyzedSounding> {
+        self.soundings.iter()
+    }
+}
+
+impl IntoIterator for OwnedSoundings {
+    type Item = AnalyzedSounding;
+    type IntoIter = IntoIter<AnalyzedSounding>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.soundings.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a OwnedSoundings {
+    type Item = &'a AnalyzedSounding;
+    type IntoIter = Iter<'a, AnalyzedSounding>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.soundings.iter()
+    }
+}
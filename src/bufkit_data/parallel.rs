@@ -0,0 +1,28 @@
+//! Parse the soundings in a bufkit file in parallel.
+
+use rayon::prelude::*;
+
+use super::{combine, next_matching_pair, AnalyzedSounding, BufkitData};
+
+impl<'a> BufkitData<'a> {
+    /// Parse the soundings in parallel.
+    ///
+    /// The upper air and surface sections are split into one chunk of text per valid time, and
+    /// the chunks are parsed in parallel. The results are the same, and in the same order, as
+    /// iterating over the `BufkitData`, so collecting into a `Vec` gives the soundings sorted by
+    /// valid time.
+    pub fn par_soundings(&self) -> impl ParallelIterator<Item = AnalyzedSounding> + '_ {
+        let (upper_air, surface) =
+            rayon::join(|| self.upper_air.par_parse(), || self.surface.par_parse());
+
+        let mut upper_air = upper_air.into_iter();
+        let mut surface = surface.into_iter();
+        let pairs: Vec<_> =
+            std::iter::from_fn(|| next_matching_pair(&mut upper_air, &mut surface)).collect();
+
+        let source_name = self.file_name;
+        pairs
+            .into_par_iter()
+            .map(move |(ua, sd)| combine::combine_data(ua, sd, source_name))
+    }
+}
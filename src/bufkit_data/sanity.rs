@@ -0,0 +1,146 @@
+//! Look for values that parse fine, but are not physically plausible.
+
+use super::{BufkitData, SurfaceData, UpperAir};
+use chrono::NaiveDateTime;
+use metfor::Quantity;
+use optional::{Noned, Optioned};
+use std::fmt;
+use std::ops::RangeInclusive;
+
+// Limits for plausible values.
+const PRESSURE_HPA: RangeInclusive<f64> = 1.0..=1100.0;
+const TEMPERATURE_C: RangeInclusive<f64> = -120.0..=60.0;
+const WIND_SPEED_KT: RangeInclusive<f64> = 0.0..=300.0;
+const FRACTION: RangeInclusive<f64> = 0.0..=1.0;
+
+/// A value that is physically implausible, which usually means the file is corrupt or a value is
+/// in the wrong units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    /// The valid time of the sounding or surface data with the value.
+    pub valid_time: NaiveDateTime,
+    /// The name of the value, e.g. "temperature" or "surface temperature".
+    pub name: &'static str,
+    /// The index of the level in the profile, or `None` for surface data.
+    pub level: Option<usize>,
+    /// The implausible value, in the units the file uses.
+    pub value: f64,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            self.valid_time.format("%Y-%m-%d %H:%MZ"),
+            self.name
+        )?;
+        if let Some(level) = self.level {
+            write!(f, " at level {}", level)?;
+        }
+        write!(f, " is out of range: {}", self.value)
+    }
+}
+
+impl<'a> BufkitData<'a> {
+    /// Look for values that are physically implausible, like a pressure of 8000 hPa.
+    ///
+    /// Pressure, temperature, dew point, wind speed, and cloud values are checked in both the
+    /// upper air and surface sections. Soundings and surface data that fail to parse are skipped,
+    /// use `validate` to find those.
+    pub fn sanity_check(&self) -> Vec<Warning> {
+        let mut warnings = vec![];
+
+        for ua in self.upper_air() {
+            check_upper_air(&ua, &mut warnings);
+        }
+        for sd in self.surface_data() {
+            check_surface(&sd, &mut warnings);
+        }
+
+        warnings
+    }
+}
+
+fn check_upper_air(ua: &UpperAir, warnings: &mut Vec<Warning>) {
+    let mut check_profile =
+        |name, vals: &mut dyn Iterator<Item = Option<f64>>, range: RangeInclusive<f64>| {
+            for (level, val) in vals.enumerate() {
+                if let Some(value) = val.filter(|val| !range.contains(val)) {
+                    warnings.push(Warning {
+                        valid_time: ua.valid_time,
+                        name,
+                        level: Some(level),
+                        value,
+                    });
+                }
+            }
+        };
+
+    check_profile("pressure", &mut unpacked(&ua.pressure), PRESSURE_HPA);
+    check_profile("temperature", &mut unpacked(&ua.temperature), TEMPERATURE_C);
+    check_profile("wet bulb", &mut unpacked(&ua.wet_bulb), TEMPERATURE_C);
+    check_profile("dew point", &mut unpacked(&ua.dew_point), TEMPERATURE_C);
+    check_profile(
+        "wind speed",
+        &mut ua
+            .wind
+            .iter()
+            .map(|wind| wind.map(|wind| wind.speed.unpack())),
+        WIND_SPEED_KT,
+    );
+    check_profile(
+        "cloud fraction",
+        &mut ua.cloud_fraction.iter().map(|cf| cf.into_option()),
+        FRACTION,
+    );
+}
+
+fn check_surface(sd: &SurfaceData, warnings: &mut Vec<Warning>) {
+    let mut check = |name, val: Option<f64>, range: RangeInclusive<f64>| {
+        if let Some(value) = val.filter(|val| !range.contains(val)) {
+            warnings.push(Warning {
+                valid_time: sd.valid_time,
+                name,
+                level: None,
+                value,
+            });
+        }
+    };
+
+    check(
+        "mean sea level pressure",
+        sd.mslp.map(|p| p.unpack()),
+        PRESSURE_HPA,
+    );
+    check(
+        "station pressure",
+        sd.station_pres.map(|p| p.unpack()),
+        PRESSURE_HPA,
+    );
+    check(
+        "surface temperature",
+        sd.temperature.map(|t| t.unpack()),
+        TEMPERATURE_C,
+    );
+    check(
+        "surface dew point",
+        sd.dewpoint.map(|t| t.unpack()),
+        TEMPERATURE_C,
+    );
+    check(
+        "surface wind speed",
+        sd.wind.map(|wind| wind.speed.unpack()),
+        WIND_SPEED_KT,
+    );
+    check("low cloud", sd.low_cloud.into_option(), FRACTION);
+    check("mid cloud", sd.mid_cloud.into_option(), FRACTION);
+    check("high cloud", sd.hi_cloud.into_option(), FRACTION);
+}
+
+fn unpacked<T>(vals: &[Optioned<T>]) -> impl Iterator<Item = Option<f64>> + '_
+where
+    T: Quantity + Noned + Copy,
+{
+    vals.iter().map(|val| val.map(|val| val.unpack()))
+}
@@ -1,47 +1,77 @@
 //! Module for parsing surface data in a bufkit file.
 
 use crate::error::*;
+use crate::parse_util::ParseOptions;
 use chrono::{NaiveDate, NaiveDateTime};
-use metfor::{Celsius, HectoPascal, Kelvin, Km, Knots, MetersPSec, Mm, WindSpdDir, WindUV};
-use optional::{none, some, Optioned};
-use std::error::Error;
+use metfor::{
+    Celsius, Fahrenheit, HectoPascal, Kelvin, Km, Knots, MetersPSec, Mm, WindSpdDir, WindUV,
+};
+use optional::{none, Optioned};
+use sounding_analysis::PrecipType;
 
-/// Surface data.
+/// The surface data for one valid time.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SurfaceData {
-    pub station_num: i32,                    // Same is in StationInfo
-    pub valid_time: NaiveDateTime,           // Always assume UTC.
-    pub mslp: Optioned<HectoPascal>,         // Surface pressure reduce to mean sea level
-    pub station_pres: Optioned<HectoPascal>, // Surface pressure
-    pub low_cloud: Optioned<f64>,            // low cloud coverage percent
-    pub mid_cloud: Optioned<f64>,            // mid cloud coverage percent
-    pub hi_cloud: Optioned<f64>,             // high cloud coverage percent
-    pub wind: Optioned<WindSpdDir<Knots>>,   // surface wind direction and speed
-    pub temperature: Optioned<Celsius>,      // 2 meter temperature C
-    pub dewpoint: Optioned<Celsius>,         // 2 meter dew point C
-
-    pub skin_temp: Optioned<Celsius>,      // Skin temperature (C)
-    pub lyr_1_soil_temp: Optioned<Kelvin>, // Layer 1 soil temperature (K)
-    pub snow_1hr: Optioned<f64>,           // 1-hour accumulated snowfall (Kg/m**2)
+    /// Station number, same as in the upper air section.
+    pub station_num: i32,
+    /// Valid time, always assume UTC.
+    pub valid_time: NaiveDateTime,
+    /// Surface pressure reduced to mean sea level.
+    pub mslp: Optioned<HectoPascal>,
+    /// Surface pressure.
+    pub station_pres: Optioned<HectoPascal>,
+    /// Low cloud coverage as a fraction from 0 to 1.
+    pub low_cloud: Optioned<f64>,
+    /// Mid cloud coverage as a fraction from 0 to 1.
+    pub mid_cloud: Optioned<f64>,
+    /// High cloud coverage as a fraction from 0 to 1.
+    pub hi_cloud: Optioned<f64>,
+    /// Surface wind direction and speed.
+    pub wind: Optioned<WindSpdDir<Knots>>,
+    /// 2 meter temperature.
+    pub temperature: Optioned<Celsius>,
+    /// 2 meter dew point.
+    pub dewpoint: Optioned<Celsius>,
+
+    /// SKTC - Skin temperature.
+    pub skin_temp: Optioned<Celsius>,
+    /// STC1 - Layer 1 soil temperature.
+    pub lyr_1_soil_temp: Optioned<Kelvin>,
+    /// SNFL - 1-hour accumulated snowfall (Kg/m**2).
+    pub snow_1hr: Optioned<f64>,
 
     // WTNS - Soil moisture availability (percent)
-    pub p01: Optioned<Mm>, // P01M - 1-hour total precipitation (mm)
-    pub c01: Optioned<Mm>, // C01M - 1-hour convective precipitation (mm)
-    pub lyr_2_soil_temp: Optioned<Kelvin>, // STC2 - Layer 2 soil temperature (K)
-    pub snow_ratio: Optioned<f64>, // SNRA - Snow ratio from explicit cloud scheme (percent)
+    /// P01M - 1-hour total precipitation.
+    pub p01: Optioned<Mm>,
+    /// C01M - 1-hour convective precipitation.
+    pub c01: Optioned<Mm>,
+    /// STC2 - Layer 2 soil temperature.
+    pub lyr_2_soil_temp: Optioned<Kelvin>,
+    /// SNRA - Snow ratio from explicit cloud scheme (percent).
+    pub snow_ratio: Optioned<f64>,
     // R01M - 1-hour accumulated surface runoff (mm)
     // BFGR - 1-hour accumulated baseflow-groundwater runoff (mm)
     // Q2MS - 2-meter specific humidity
-    pub snow_type: Option<bool>, // WXTS - Snow precipitation type (1=Snow)
-    pub ice_pellets_type: Option<bool>, // WXTP - Ice pellets precipitation type (1=Ice pellets)
-    pub fzra_type: Option<bool>, // WXTZ - Freezing rain precipitation type (1=Freezing rain)
-    pub rain_type: Option<bool>, // WXTR - Rain precipitation type (1=Rain)
-    pub storm_motion: Optioned<WindUV<MetersPSec>>, // Storm motion (m/s)
-    pub srh: Optioned<f64>,      // HLCY - Storm relative helicity (m**2/s**2)
+    /// WXTS - Snow precipitation type.
+    pub snow_type: Option<bool>,
+    /// WXTP - Ice pellets precipitation type.
+    pub ice_pellets_type: Option<bool>,
+    /// WXTZ - Freezing rain precipitation type.
+    pub fzra_type: Option<bool>,
+    /// WXTR - Rain precipitation type.
+    pub rain_type: Option<bool>,
+    /// USTM and VSTM - Storm motion.
+    pub storm_motion: Optioned<WindUV<MetersPSec>>,
+    /// HLCY - Storm relative helicity (m**2/s**2).
+    pub srh: Optioned<f64>,
     // SLLH - 1-hour surface evaporation (mm)
-    pub wx_sym_cod: Optioned<f64>, // WSYM - Weather type symbol number
-    // CDBP - Pressure at the base of cloud (hPa)
-    pub visibility: Optioned<Km>, // VSBK - Visibility (km)
+    /// WSYM - Weather type symbol number.
+    pub wx_sym_cod: Optioned<f64>,
+    /// CDBP - Pressure at the base of cloud.
+    pub cloud_base_pres: Optioned<HectoPascal>,
+    /// VSBK - Visibility.
+    pub visibility: Optioned<Km>,
 }
 
 impl SurfaceData {
@@ -49,10 +79,10 @@ impl SurfaceData {
     ///
     /// This function does not match all possible column names. Much more work would need to be
     /// done for that, but there are some relavent links in the bufkit_parameters.txt file.
-    pub fn parse_columns(header: &str) -> Result<SfcColumns, BufkitFileError> {
+    pub(crate) fn parse_columns(header: &str) -> Result<SfcColumns, BufkitFileError> {
         use self::SfcColName::*;
 
-        let cols_text = header.trim().split_whitespace();
+        let cols_text = header.split_whitespace();
 
         let mut cols = SfcColumns {
             names: Vec::with_capacity(33),
@@ -86,6 +116,7 @@ impl SurfaceData {
                 "VSTM" => cols.names.push(VSTM),
                 "HLCY" => cols.names.push(HLCY),
                 "WSYM" => cols.names.push(WSYM),
+                "CDBP" => cols.names.push(CDBP),
                 "VSBK" => cols.names.push(VSBK),
                 _ => cols.names.push(NONE),
             }
@@ -94,10 +125,11 @@ impl SurfaceData {
         // Check that we found some required columns.
         {
             let names: &Vec<_> = &cols.names;
-            if names.iter().find(|&&x| x == STN).is_none()
-                || names.iter().find(|&&x| x == VALIDTIME).is_none()
-            {
-                return Err(BufkitFileError::new());
+            if !names.contains(&STN) {
+                return Err(BufkitFileError::MissingColumn("STN".to_owned()));
+            }
+            if !names.contains(&VALIDTIME) {
+                return Err(BufkitFileError::MissingColumn("YYMMDD/HHMM".to_owned()));
             }
         }
 
@@ -105,7 +137,37 @@ impl SurfaceData {
     }
 
     /// Parse a few values stored as strings in the `tokens` iterator.
-    pub fn parse_values(tokens: &str, cols: &SfcColumns) -> Result<SurfaceData, Box<dyn Error>> {
+    ///
+    /// Errors have the line number of the value that failed to parse, relative to the start of
+    /// `tokens`.
+    pub(crate) fn parse_values(
+        tokens: &str,
+        cols: &SfcColumns,
+        options: &ParseOptions,
+    ) -> Result<SurfaceData, BufkitFileError> {
+        use crate::parse_util::line_of;
+
+        let mut last_token = tokens;
+        SurfaceData::parse_tokens(tokens, cols, options, &mut last_token).map_err(|err| {
+            // Errors converted from the standard library don't know which token failed to parse.
+            let err = match err {
+                BufkitFileError::ParseFloat(_) => {
+                    BufkitFileError::ParseFloat(last_token.to_owned())
+                }
+                BufkitFileError::ParseInt(_) => BufkitFileError::ParseInt(last_token.to_owned()),
+                err => err,
+            };
+            err.located(line_of(tokens, last_token))
+        })
+    }
+
+    /// Parse the values, keeping track of the last token tried in `last_token`.
+    fn parse_tokens<'a>(
+        tokens: &'a str,
+        cols: &SfcColumns,
+        options: &ParseOptions,
+        last_token: &mut &'a str,
+    ) -> Result<SurfaceData, BufkitFileError> {
         use std::str::FromStr;
         let mut tokens = tokens.split_whitespace();
 
@@ -120,68 +182,56 @@ impl SurfaceData {
         for i in 0..cols.num_cols() {
             if let Some(token) = tokens.next() {
                 use self::SfcColName::*;
+                *last_token = token;
                 use crate::parse_util::*;
+                let name = cols.names[i];
+                let missing = |val| {
+                    if name.missing_value() == Some(val) {
+                        none()
+                    } else {
+                        options.check_missing(val)
+                    }
+                };
                 let _dummy: f64; // Used just to check that there is a valid value there.
 
-                match cols.names[i] {
+                match name {
                     NONE => _dummy = f64::from_str(token)?,
                     STN => sd.station_num = i32::from_str(token)?,
                     VALIDTIME => sd.valid_time = parse_naive_date_time(token)?,
-                    PMSL => sd.mslp = check_missing(f64::from_str(token)?).map_t(HectoPascal),
-                    PRES => {
-                        sd.station_pres = check_missing(f64::from_str(token)?).map_t(HectoPascal)
-                    }
-                    LCLD => {
-                        sd.low_cloud = check_missing(f64::from_str(token)?).map_t(|val| val / 100.0)
-                    }
-                    MCLD => {
-                        sd.mid_cloud = check_missing(f64::from_str(token)?).map_t(|val| val / 100.0)
-                    }
-                    HCLD => {
-                        sd.hi_cloud = check_missing(f64::from_str(token)?).map_t(|val| val / 100.0)
-                    }
-                    UWND => u_wind = check_missing(f64::from_str(token)?).map_t(MetersPSec),
-                    VWND => v_wind = check_missing(f64::from_str(token)?).map_t(MetersPSec),
-                    T2MS => sd.temperature = check_missing(f64::from_str(token)?).map_t(Celsius),
-                    TD2M => sd.dewpoint = check_missing(f64::from_str(token)?).map_t(Celsius),
-                    SKTC => sd.skin_temp = check_missing(f64::from_str(token)?).map_t(Celsius),
-                    STC1 => sd.lyr_1_soil_temp = check_missing(f64::from_str(token)?).map_t(Kelvin),
-                    SNFL => sd.snow_1hr = check_missing(f64::from_str(token)?),
-                    P01M => sd.p01 = check_missing(f64::from_str(token)?).map_t(Mm),
-                    C01M => sd.c01 = check_missing(f64::from_str(token)?).map_t(Mm),
-                    STC2 => sd.lyr_2_soil_temp = check_missing(f64::from_str(token)?).map_t(Kelvin),
-                    SNRA => sd.snow_ratio = check_missing(f64::from_str(token)?),
-                    WXTS => {
-                        sd.snow_type = check_missing(f64::from_str(token)?).map(|val| val > 0.5)
-                    }
+                    PMSL => sd.mslp = missing(f64::from_str(token)?).map_t(HectoPascal),
+                    PRES => sd.station_pres = missing(f64::from_str(token)?).map_t(HectoPascal),
+                    LCLD => sd.low_cloud = missing(f64::from_str(token)?).map_t(|val| val / 100.0),
+                    MCLD => sd.mid_cloud = missing(f64::from_str(token)?).map_t(|val| val / 100.0),
+                    HCLD => sd.hi_cloud = missing(f64::from_str(token)?).map_t(|val| val / 100.0),
+                    UWND => u_wind = missing(f64::from_str(token)?).map_t(MetersPSec),
+                    VWND => v_wind = missing(f64::from_str(token)?).map_t(MetersPSec),
+                    T2MS => sd.temperature = missing(f64::from_str(token)?).map_t(Celsius),
+                    TD2M => sd.dewpoint = missing(f64::from_str(token)?).map_t(Celsius),
+                    SKTC => sd.skin_temp = missing(f64::from_str(token)?).map_t(Celsius),
+                    STC1 => sd.lyr_1_soil_temp = missing(f64::from_str(token)?).map_t(Kelvin),
+                    SNFL => sd.snow_1hr = missing(f64::from_str(token)?),
+                    P01M => sd.p01 = missing(f64::from_str(token)?).map_t(Mm),
+                    C01M => sd.c01 = missing(f64::from_str(token)?).map_t(Mm),
+                    STC2 => sd.lyr_2_soil_temp = missing(f64::from_str(token)?).map_t(Kelvin),
+                    SNRA => sd.snow_ratio = missing(f64::from_str(token)?),
+                    WXTS => sd.snow_type = missing(f64::from_str(token)?).map(|val| val > 0.5),
                     WXTP => {
-                        sd.ice_pellets_type =
-                            check_missing(f64::from_str(token)?).map(|val| val > 0.5)
-                    }
-                    WXTZ => {
-                        sd.fzra_type = check_missing(f64::from_str(token)?).map(|val| val > 0.5)
-                    }
-                    WXTR => {
-                        sd.rain_type = check_missing(f64::from_str(token)?).map(|val| val > 0.5)
-                    }
-                    USTM => u_storm = check_missing(f64::from_str(token)?).map_t(MetersPSec),
-                    VSTM => v_storm = check_missing(f64::from_str(token)?).map_t(MetersPSec),
-                    HLCY => sd.srh = check_missing(f64::from_str(token)?),
-                    WSYM => {
-                        sd.wx_sym_cod = if let Ok(val) = f64::from_str(token) {
-                            if val == MISSING_F64_INDEX || val == MISSING_F64 {
-                                none()
-                            } else {
-                                some(val)
-                            }
-                        } else {
-                            none()
-                        }
+                        sd.ice_pellets_type = missing(f64::from_str(token)?).map(|val| val > 0.5)
                     }
-                    VSBK => sd.visibility = check_missing(f64::from_str(token)?).map_t(Km),
+                    WXTZ => sd.fzra_type = missing(f64::from_str(token)?).map(|val| val > 0.5),
+                    WXTR => sd.rain_type = missing(f64::from_str(token)?).map(|val| val > 0.5),
+                    USTM => u_storm = missing(f64::from_str(token)?).map_t(MetersPSec),
+                    VSTM => v_storm = missing(f64::from_str(token)?).map_t(MetersPSec),
+                    HLCY => sd.srh = missing(f64::from_str(token)?),
+                    WSYM => sd.wx_sym_cod = f64::from_str(token).map_or(none(), missing),
+                    CDBP => sd.cloud_base_pres = missing(f64::from_str(token)?).map_t(HectoPascal),
+                    VSBK => sd.visibility = missing(f64::from_str(token)?).map_t(Km),
                 };
             } else {
-                return Err(BufkitFileError::new().into());
+                return Err(BufkitFileError::ShortRow {
+                    expected: cols.num_cols(),
+                    found: i,
+                });
             }
         }
 
@@ -192,11 +242,271 @@ impl SurfaceData {
     }
 }
 
+impl SurfaceData {
+    /// Parse only the valid time from a string of tokens, skipping all the other values.
+    pub(crate) fn parse_valid_time(
+        tokens: &str,
+        cols: &SfcColumns,
+    ) -> Result<NaiveDateTime, BufkitFileError> {
+        use crate::parse_util::parse_naive_date_time;
+
+        let token = SurfaceData::column_token(tokens, cols, SfcColName::VALIDTIME, "YYMMDD/HHMM")?;
+        parse_naive_date_time(token)
+    }
+
+    /// Parse only the station number from a string of tokens, skipping all the other values.
+    pub(crate) fn parse_station_num(
+        tokens: &str,
+        cols: &SfcColumns,
+    ) -> Result<i32, BufkitFileError> {
+        use std::str::FromStr;
+
+        let token = SurfaceData::column_token(tokens, cols, SfcColName::STN, "STN")?;
+        Ok(i32::from_str(token)?)
+    }
+
+    /// Find the token in the column `name`, which is called `label` in errors.
+    fn column_token<'a>(
+        tokens: &'a str,
+        cols: &SfcColumns,
+        name: SfcColName,
+        label: &str,
+    ) -> Result<&'a str, BufkitFileError> {
+        let idx = cols
+            .position(name)
+            .ok_or_else(|| BufkitFileError::MissingColumn(label.to_owned()))?;
+
+        tokens
+            .split_whitespace()
+            .nth(idx)
+            .ok_or(BufkitFileError::ShortRow {
+                expected: cols.num_cols(),
+                found: idx,
+            })
+    }
+}
+
+impl SurfaceData {
+    /// Get the 2 meter temperature in Fahrenheit.
+    pub fn temperature_f(&self) -> Optioned<Fahrenheit> {
+        self.temperature.map_t(Fahrenheit::from)
+    }
+
+    /// Get the 2 meter dew point in Fahrenheit.
+    pub fn dewpoint_f(&self) -> Optioned<Fahrenheit> {
+        self.dewpoint.map_t(Fahrenheit::from)
+    }
+
+    /// Get the storm motion as the direction it is moving from and its speed.
+    ///
+    /// The `storm_motion` field has the same vector as U and V components.
+    pub fn storm_motion_spd_dir(&self) -> Optioned<WindSpdDir<MetersPSec>> {
+        self.storm_motion.map_t(WindSpdDir::from)
+    }
+
+    /// Get the wind as U and V components in m/s.
+    ///
+    /// A calm wind has no meaningful direction, so it is always `(0, 0)` no matter what direction
+    /// is stored with it.
+    pub fn wind_uv_mps(&self) -> Optioned<WindUV<MetersPSec>> {
+        self.wind.map_t(|wind| {
+            if wind.speed <= Knots(0.0) {
+                WindUV {
+                    u: MetersPSec(0.0),
+                    v: MetersPSec(0.0),
+                }
+            } else {
+                WindUV::from(wind)
+            }
+        })
+    }
+}
+
+impl SurfaceData {
+    /// Get the type of precipitation, if there is any.
+    ///
+    /// The weather symbol code (WSYM) is used if it is a recognized code. Otherwise it falls back
+    /// to the precipitation type flags. When more than one flag is set, freezing rain wins, then
+    /// ice pellets, then rain and snow together are a mix.
+    pub fn precip_type(&self) -> Option<PrecipType> {
+        self.wx_sym_cod
+            .into_option()
+            .and_then(|code| precip_type_from_wx_code(code as u8))
+            .or_else(|| self.precip_type_from_flags())
+    }
+
+    /// Resolve the precipitation type flags into a single type.
+    fn precip_type_from_flags(&self) -> Option<PrecipType> {
+        let is_set = |flag: Option<bool>| flag.unwrap_or(false);
+
+        let rain = is_set(self.rain_type);
+        let snow = is_set(self.snow_type);
+
+        if is_set(self.fzra_type) {
+            Some(PrecipType::LightFreezingRain)
+        } else if is_set(self.ice_pellets_type) {
+            Some(PrecipType::LightIcePellets)
+        } else if rain && snow {
+            Some(PrecipType::LightRainAndSnow)
+        } else if snow {
+            Some(PrecipType::LightSnow)
+        } else if rain {
+            Some(PrecipType::LightRain)
+        } else {
+            None
+        }
+    }
+}
+
+/// Translate a bufkit weather symbol code into the kind that is used in sounding-analysis.
+///
+/// The codes are the WMO present weather (ww) codes from code table 4677. Codes 00-49 have no
+/// precipitation at the time of observation, so they aren't translated.
+///
+/// | ww     | WMO description                                        | `PrecipType`              |
+/// |--------|--------------------------------------------------------|---------------------------|
+/// | 50, 51 | Slight drizzle                                         | `LightDrizzle`            |
+/// | 52, 53 | Moderate drizzle                                       | `ModerateDrizzle`         |
+/// | 54, 55 | Heavy drizzle                                          | `HeavyDrizzle`            |
+/// | 56     | Slight freezing drizzle                                | `LightFreezingDrizzle`    |
+/// | 57     | Moderate or heavy freezing drizzle                     | `ModerateFreezingDrizzle` |
+/// | 58     | Slight drizzle and rain                                | `LightDrizzleAndRain`     |
+/// | 59     | Moderate or heavy drizzle and rain                     | `ModerateDrizzleAndRain`  |
+/// | 60, 61 | Slight rain                                            | `LightRain`               |
+/// | 62, 63 | Moderate rain                                          | `ModerateRain`            |
+/// | 64, 65 | Heavy rain                                             | `HeavyRain`               |
+/// | 66     | Slight freezing rain                                   | `LightFreezingRain`       |
+/// | 67     | Moderate or heavy freezing rain                        | `ModerateFreezingRain`    |
+/// | 68     | Slight rain or drizzle and snow                        | `LightRainAndSnow`        |
+/// | 69     | Moderate or heavy rain or drizzle and snow             | `ModerateRainAndSnow`     |
+/// | 70, 71 | Slight snow                                            | `LightSnow`               |
+/// | 72, 73 | Moderate snow                                          | `ModerateSnow`            |
+/// | 74, 75 | Heavy snow                                             | `HeavySnow`               |
+/// | 76, 78 | Diamond dust, isolated star-like snow crystals         | `IceCrystals`             |
+/// | 77     | Snow grains                                            | `SnowGrains`              |
+/// | 79     | Ice pellets                                            | `LightIcePellets`         |
+/// | 80     | Slight rain showers                                    | `LightRainShowers`        |
+/// | 81     | Moderate or heavy rain showers                         | `ModerateRainShowers`     |
+/// | 82     | Violent rain showers                                   | `ViolentRainShowers`      |
+/// | 83     | Slight showers of rain and snow                        | `LightRainAndSnow`        |
+/// | 84     | Moderate or heavy showers of rain and snow             | `ModerateRainAndSnow`     |
+/// | 85     | Slight snow showers                                    | `LightSnowShowers`        |
+/// | 86     | Moderate or heavy snow showers                         | `ModerateSnowShowers`     |
+/// | 87     | Slight showers of snow pellets or small hail           | `LightIcePellets`         |
+/// | 88     | Moderate or heavy showers of snow pellets or small hail| `ModerateIcePellets`      |
+/// | 89, 90 | Showers of hail                                        | `Hail`                    |
+/// | 91     | Slight rain, thunderstorm in the past hour             | `LightRain`               |
+/// | 92     | Moderate or heavy rain, thunderstorm in the past hour  | `ModerateRain`            |
+/// | 93     | Slight snow or hail, thunderstorm in the past hour     | `LightSnow`               |
+/// | 94     | Moderate or heavy snow or hail, thunderstorm in the past hour | `ModerateSnow`     |
+/// | 95     | Slight or moderate thunderstorm with rain or snow      | `ModerateRainShowers`     |
+/// | 96, 99 | Thunderstorm with hail                                 | `Hail`                    |
+/// | 97     | Heavy thunderstorm with rain or snow                   | `HeavyRainShowers`        |
+/// | 98     | Thunderstorm with duststorm or sandstorm               | `Unknown`                 |
+fn precip_type_from_wx_code(wx_code: u8) -> Option<PrecipType> {
+    use sounding_analysis::PrecipType::{
+        Hail, HeavyDrizzle, HeavyRain, HeavyRainShowers, HeavySnow, IceCrystals, LightDrizzle,
+        LightDrizzleAndRain, LightFreezingDrizzle, LightFreezingRain, LightIcePellets, LightRain,
+        LightRainAndSnow, LightRainShowers, LightSnow, LightSnowShowers, ModerateDrizzle,
+        ModerateDrizzleAndRain, ModerateFreezingDrizzle, ModerateFreezingRain, ModerateIcePellets,
+        ModerateRain, ModerateRainAndSnow, ModerateRainShowers, ModerateSnow, ModerateSnowShowers,
+        SnowGrains, Unknown, ViolentRainShowers,
+    };
+
+    let precip_type = match wx_code {
+        // Drizzle
+        50 | 51 => LightDrizzle,
+        52 | 53 => ModerateDrizzle,
+        54 | 55 => HeavyDrizzle,
+        56 => LightFreezingDrizzle,
+        57 => ModerateFreezingDrizzle,
+        58 => LightDrizzleAndRain,
+        59 => ModerateDrizzleAndRain,
+
+        // Rain
+        60 | 61 => LightRain,
+        62 | 63 => ModerateRain,
+        64 | 65 => HeavyRain,
+        66 => LightFreezingRain,
+        67 => ModerateFreezingRain,
+        68 => LightRainAndSnow,
+        69 => ModerateRainAndSnow,
+
+        // Solid precipitation not in showers
+        70 | 71 => LightSnow,
+        72 | 73 => ModerateSnow,
+        74 | 75 => HeavySnow,
+        76 | 78 => IceCrystals,
+        77 => SnowGrains,
+        79 => LightIcePellets,
+
+        // Showers
+        80 => LightRainShowers,
+        81 => ModerateRainShowers,
+        82 => ViolentRainShowers,
+        83 => LightRainAndSnow,
+        84 => ModerateRainAndSnow,
+        85 => LightSnowShowers,
+        86 => ModerateSnowShowers,
+        87 => LightIcePellets,
+        88 => ModerateIcePellets,
+        89 | 90 => Hail,
+
+        // Thunderstorms
+        91 => LightRain,
+        92 => ModerateRain,
+        93 => LightSnow,
+        94 => ModerateSnow,
+        95 => ModerateRainShowers,
+        96 | 99 => Hail,
+        97 => HeavyRainShowers,
+        98 => Unknown,
+
+        _ => return None,
+    };
+
+    Some(precip_type)
+}
+
+impl std::fmt::Display for SurfaceData {
+    /// A one line summary with the valid time, pressure, temperature, dew point, and wind.
+    ///
+    /// Missing values are shown as `--`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use metfor::Quantity;
+
+        let value = |val: Option<f64>, units: &str| match val {
+            Some(val) => format!("{:.1} {}", val, units),
+            None => "--".to_owned(),
+        };
+
+        let wind = match self.wind.into_option() {
+            Some(WindSpdDir {
+                speed: Knots(speed),
+                direction,
+            }) => format!("{:03.0}/{:.0} kt", direction, speed),
+            None => "--".to_owned(),
+        };
+
+        write!(
+            f,
+            "{} MSLP {}, T {}, Td {}, wind {}",
+            self.valid_time.format("%Y-%m-%d %H:%MZ"),
+            value(self.mslp.map(|p| p.unpack()), "hPa"),
+            value(self.temperature.map(|t| t.unpack()), "C"),
+            value(self.dewpoint.map(|t| t.unpack()), "C"),
+            wind
+        )
+    }
+}
+
 impl Default for SurfaceData {
     fn default() -> SurfaceData {
         SurfaceData {
-            station_num: ::std::i32::MIN,
-            valid_time: NaiveDate::from_ymd(0, 1, 1).and_hms(0, 0, 0),
+            station_num: i32::MIN,
+            valid_time: NaiveDate::from_ymd_opt(0, 1, 1)
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .unwrap(),
             mslp: none(),
             station_pres: none(),
             low_cloud: none(),
@@ -219,44 +529,94 @@ impl Default for SurfaceData {
             storm_motion: none(),
             srh: none(),
             wx_sym_cod: none(),
+            cloud_base_pres: none(),
             visibility: none(),
         }
     }
 }
 
-/// There are many more columns that can be parsed, but these are all being parsed for now.
+/// The surface columns the parser understands.
+///
+/// There are many more columns that can be parsed, but these are all being parsed for now. Any
+/// other column is mapped to `NONE` and ignored.
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum SfcColName {
+#[non_exhaustive]
+pub enum SfcColName {
+    /// A column that is not parsed.
     NONE,
+    /// Station number
     STN,
+    /// Valid time
     VALIDTIME,
-    PMSL, // Mean sea level pressure
-    PRES, // Station pressure
-    LCLD, // Low cloud amount
-    MCLD, // Mid-level cloud amount
-    HCLD, // High cloud amount
-    UWND, // U-component of the wind
-    VWND, // V-component of the wind
-    T2MS, // 2 Meter temperature
-    TD2M, // 2 Meter dew point
-    SKTC, // Skin temperature
-    STC1, // layer 1 soil temperature
-    SNFL, // 1-hour snow fall kg/m^2
-    P01M, // 1-hour total precipitation (mm)
-    C01M, // 1-hour convective precipitation (mm)
-    STC2, // Layer 2 soil temperature (K)
-    SNRA, // Snow ratio from explicit cloud scheme (percent)
-    WXTS, // Snow weather type
-    WXTP, // Ice pellets weather type
-    WXTZ, // Freezing rain weather type,
-    WXTR, // Rain weather type,
-    USTM, // USTM - U-component of storm motion (m/s)
-    VSTM, // VSTM - V-component of storm motion (m/s)
-    HLCY, // HLCY - Storm relative helicity (m**2/s**2)
-    WSYM, // WSYM - Weather type symbol number
-    VSBK, // VSBK - Visibility (km)
+    /// Mean sea level pressure
+    PMSL,
+    /// Station pressure
+    PRES,
+    /// Low cloud amount
+    LCLD,
+    /// Mid-level cloud amount
+    MCLD,
+    /// High cloud amount
+    HCLD,
+    /// U-component of the wind
+    UWND,
+    /// V-component of the wind
+    VWND,
+    /// 2 Meter temperature
+    T2MS,
+    /// 2 Meter dew point
+    TD2M,
+    /// Skin temperature
+    SKTC,
+    /// Layer 1 soil temperature
+    STC1,
+    /// 1-hour snow fall kg/m^2
+    SNFL,
+    /// 1-hour total precipitation (mm)
+    P01M,
+    /// 1-hour convective precipitation (mm)
+    C01M,
+    /// Layer 2 soil temperature (K)
+    STC2,
+    /// Snow ratio from explicit cloud scheme (percent)
+    SNRA,
+    /// Snow weather type
+    WXTS,
+    /// Ice pellets weather type
+    WXTP,
+    /// Freezing rain weather type
+    WXTZ,
+    /// Rain weather type
+    WXTR,
+    /// U-component of storm motion (m/s)
+    USTM,
+    /// V-component of storm motion (m/s)
+    VSTM,
+    /// Storm relative helicity (m**2/s**2)
+    HLCY,
+    /// Weather type symbol number
+    WSYM,
+    /// Pressure at the base of cloud (hPa)
+    CDBP,
+    /// Visibility (km)
+    VSBK,
 }
 
+impl SfcColName {
+    /// The value this column uses for missing or unlimited values, in addition to the usual
+    /// missing values.
+    fn missing_value(self) -> Option<f64> {
+        use crate::parse_util::MISSING_F64_INDEX;
+
+        match self {
+            SfcColName::WSYM | SfcColName::CDBP | SfcColName::VSBK => Some(MISSING_F64_INDEX),
+            _ => None,
+        }
+    }
+}
+
+/// The columns in the surface section, in the order they appear in the header.
 #[derive(Debug)]
 pub struct SfcColumns {
     names: Vec<SfcColName>,
@@ -267,6 +627,79 @@ impl SfcColumns {
     pub fn num_cols(&self) -> usize {
         self.names.len()
     }
+
+    /// Get the columns in the order they appear in the header, unrecognized columns are `NONE`.
+    pub fn column_names(&self) -> &[SfcColName] {
+        &self.names
+    }
+
+    /// Get the number of columns the parser understands.
+    pub fn num_recognized(&self) -> usize {
+        self.num_cols() - self.num_ignored()
+    }
+
+    /// Get the number of columns that are mapped to `NONE` and dropped while parsing.
+    pub fn num_ignored(&self) -> usize {
+        self.names
+            .iter()
+            .filter(|&&col| col == SfcColName::NONE)
+            .count()
+    }
+
+    /// Check that `text` starts with a record, which means the tokens in the station number and
+    /// valid time columns are actually a station number and a valid time.
+    pub(crate) fn starts_with_record(&self, text: &str) -> bool {
+        use crate::parse_util::parse_naive_date_time;
+        use std::str::FromStr;
+
+        let tokens: Vec<&str> = text.split_whitespace().take(self.num_cols()).collect();
+
+        let stn_ok = self
+            .position(SfcColName::STN)
+            .and_then(|idx| tokens.get(idx))
+            .map(|token| i32::from_str(token).is_ok())
+            .unwrap_or(false);
+
+        let time_ok = self
+            .position(SfcColName::VALIDTIME)
+            .and_then(|idx| tokens.get(idx))
+            .map(|token| parse_naive_date_time(token).is_ok())
+            .unwrap_or(false);
+
+        stn_ok && time_ok
+    }
+
+    /// Find the byte offset of the start of the next record in `text` after the first token.
+    ///
+    /// This is used to resynchronize after finding a record with too few or too many values.
+    pub(crate) fn find_next_record_start(&self, text: &str) -> Option<usize> {
+        let token_starts: Vec<usize> = text
+            .split_whitespace()
+            .map(|token| token.as_ptr() as usize - text.as_ptr() as usize)
+            .collect();
+
+        token_starts
+            .iter()
+            .skip(1)
+            .map(|&start| &text[start..])
+            .position(|sub_text| self.starts_with_record(sub_text))
+            .map(|idx| token_starts[idx + 1])
+    }
+
+    /// Find the byte offset of the start of the last record in `text`.
+    ///
+    /// This is the reverse of `find_next_record_start`, for resynchronizing while iterating
+    /// backward.
+    pub(crate) fn find_last_record_start(&self, text: &str) -> Option<usize> {
+        text.split_whitespace()
+            .map(|token| token.as_ptr() as usize - text.as_ptr() as usize)
+            .rev()
+            .find(|&start| self.starts_with_record(&text[start..]))
+    }
+
+    fn position(&self, name: SfcColName) -> Option<usize> {
+        self.names.iter().position(|&col| col == name)
+    }
 }
 
 // STN  - 6-digit station number
@@ -308,6 +741,7 @@ impl SfcColumns {
 #[cfg(test)]
 mod test {
     use super::*;
+    use optional::some;
 
     #[test]
     fn test_parse_columns() {
@@ -321,26 +755,25 @@ mod test {
         assert_eq!(col_idx.num_cols(), 23);
 
         for i in 1..col_idx.names.len() {
-            let col_name: SfcColName;
-            match i {
-                0 => col_name = STN,
-                1 => col_name = VALIDTIME,
-                2 => col_name = PMSL,
-                3 => col_name = PRES,
-                4 => col_name = SKTC,
-                5 => col_name = STC1,
-                10 => col_name = LCLD,
-                11 => col_name = MCLD,
-                12 => col_name = HCLD,
-                13 => col_name = UWND,
-                14 => col_name = VWND,
-                15 => col_name = T2MS,
-                17 => col_name = WXTS,
-                18 => col_name = WXTP,
-                19 => col_name = WXTZ,
-                20 => col_name = WXTR,
-                22 => col_name = TD2M,
-                _ => col_name = NONE,
+            let col_name = match i {
+                0 => STN,
+                1 => VALIDTIME,
+                2 => PMSL,
+                3 => PRES,
+                4 => SKTC,
+                5 => STC1,
+                10 => LCLD,
+                11 => MCLD,
+                12 => HCLD,
+                13 => UWND,
+                14 => VWND,
+                15 => T2MS,
+                17 => WXTS,
+                18 => WXTP,
+                19 => WXTZ,
+                20 => WXTR,
+                22 => TD2M,
+                _ => NONE,
             };
 
             assert_eq!(col_idx.names[i], col_name);
@@ -353,44 +786,45 @@ mod test {
         let col_idx = SurfaceData::parse_columns(test_data).unwrap();
 
         assert_eq!(col_idx.num_cols(), 33);
+        assert_eq!(col_idx.num_recognized(), 28);
+        assert_eq!(col_idx.num_ignored(), 5);
 
         for i in 1..col_idx.names.len() {
-            let col_name: SfcColName;
-            match i {
-                0 => col_name = STN,
-                1 => col_name = VALIDTIME,
-                2 => col_name = PMSL,
-                3 => col_name = PRES,
-                4 => col_name = SKTC,
-                5 => col_name = STC1,
-                6 => col_name = SNFL,
-
-                8 => col_name = P01M,
-                9 => col_name = C01M,
-                10 => col_name = STC2,
-                11 => col_name = LCLD,
-                12 => col_name = MCLD,
-                13 => col_name = HCLD,
-                14 => col_name = SNRA,
-                15 => col_name = UWND,
-                16 => col_name = VWND,
-
-                19 => col_name = T2MS,
-
-                21 => col_name = WXTS,
-                22 => col_name = WXTP,
-                23 => col_name = WXTZ,
-                24 => col_name = WXTR,
-                25 => col_name = USTM,
-                26 => col_name = VSTM,
-                27 => col_name = HLCY,
-
-                29 => col_name = WSYM,
-
-                31 => col_name = VSBK,
-                32 => col_name = TD2M,
-
-                _ => col_name = NONE,
+            let col_name = match i {
+                0 => STN,
+                1 => VALIDTIME,
+                2 => PMSL,
+                3 => PRES,
+                4 => SKTC,
+                5 => STC1,
+                6 => SNFL,
+
+                8 => P01M,
+                9 => C01M,
+                10 => STC2,
+                11 => LCLD,
+                12 => MCLD,
+                13 => HCLD,
+                14 => SNRA,
+                15 => UWND,
+                16 => VWND,
+
+                19 => T2MS,
+
+                21 => WXTS,
+                22 => WXTP,
+                23 => WXTZ,
+                24 => WXTR,
+                25 => USTM,
+                26 => VSTM,
+                27 => HLCY,
+
+                29 => WSYM,
+                30 => CDBP,
+                31 => VSBK,
+                32 => TD2M,
+
+                _ => NONE,
             };
 
             assert_eq!(col_idx.names[i], col_name);
@@ -403,4 +837,304 @@ mod test {
 
         assert!(SurfaceData::parse_columns(test_data).is_err());
     }
+
+    #[test]
+    fn test_column_missing_values() {
+        let cols = SurfaceData::parse_columns("STN YYMMDD/HHMM WSYM CDBP VSBK").unwrap();
+        let options = ParseOptions::default();
+
+        let sd =
+            SurfaceData::parse_values("727730 170401/0000 999.00 999.00 999.00", &cols, &options)
+                .unwrap();
+        assert!(sd.wx_sym_cod.is_none());
+        assert!(sd.cloud_base_pres.is_none());
+        assert!(sd.visibility.is_none());
+
+        let sd = SurfaceData::parse_values(
+            "727730 170401/0000 -9999.00 -9999.00 -9999.00",
+            &cols,
+            &options,
+        )
+        .unwrap();
+        assert!(sd.wx_sym_cod.is_none());
+        assert!(sd.cloud_base_pres.is_none());
+        assert!(sd.visibility.is_none());
+
+        let sd =
+            SurfaceData::parse_values("727730 170401/0000 71.00 801.20 20.00", &cols, &options)
+                .unwrap();
+        assert_eq!(sd.wx_sym_cod, some(71.0));
+        assert_eq!(sd.cloud_base_pres, some(HectoPascal(801.2)));
+        assert_eq!(sd.visibility, some(Km(20.0)));
+    }
+
+    #[test]
+    fn test_fahrenheit() {
+        let sd = SurfaceData {
+            temperature: some(Celsius(20.0)),
+            dewpoint: some(Celsius(-40.0)),
+            ..SurfaceData::default()
+        };
+        assert_eq!(sd.temperature_f(), some(Fahrenheit(68.0)));
+        assert_eq!(sd.dewpoint_f(), some(Fahrenheit(-40.0)));
+
+        let sd = SurfaceData::default();
+        assert!(sd.temperature_f().is_none());
+        assert!(sd.dewpoint_f().is_none());
+    }
+
+    #[test]
+    fn test_storm_motion_spd_dir() {
+        let spd_dir = |u: f64, v: f64| {
+            let sd = SurfaceData {
+                storm_motion: some(WindUV {
+                    u: MetersPSec(u),
+                    v: MetersPSec(v),
+                }),
+                ..SurfaceData::default()
+            };
+            sd.storm_motion_spd_dir().unwrap()
+        };
+
+        // Moving toward the east is a storm motion from the west.
+        let WindSpdDir { speed, direction } = spd_dir(10.0, 0.0);
+        assert!((speed.0 - 10.0).abs() < 1.0e-9, "speed = {:?}", speed);
+        assert!(
+            (direction - 270.0).abs() < 1.0e-9,
+            "direction = {}",
+            direction
+        );
+
+        // Moving toward the northeast is a storm motion from the southwest.
+        let WindSpdDir { speed, direction } = spd_dir(3.0, 3.0);
+        assert!(
+            (speed.0 - 18.0f64.sqrt()).abs() < 1.0e-9,
+            "speed = {:?}",
+            speed
+        );
+        assert!(
+            (direction - 225.0).abs() < 1.0e-9,
+            "direction = {}",
+            direction
+        );
+
+        assert!(SurfaceData::default().storm_motion_spd_dir().is_none());
+    }
+
+    #[test]
+    fn test_wind_uv_mps() {
+        use metfor::Quantity;
+
+        let uv = |direction: f64, speed: f64| {
+            let sd = SurfaceData {
+                wind: some(WindSpdDir {
+                    speed: Knots(speed),
+                    direction,
+                }),
+                ..SurfaceData::default()
+            };
+            let WindUV { u, v } = sd.wind_uv_mps().unwrap();
+            (u.unpack(), v.unpack())
+        };
+
+        // 10 knots is 5.144 m/s. A west wind blows toward the east, so u is positive.
+        let (u, v) = uv(270.0, 10.0);
+        assert!((u - 5.144).abs() < 0.001, "u = {}", u);
+        assert!(v.abs() < 0.001, "v = {}", v);
+
+        // A north wind blows toward the south, so v is negative.
+        let (u, v) = uv(360.0, 10.0);
+        assert!(u.abs() < 0.001, "u = {}", u);
+        assert!((v + 5.144).abs() < 0.001, "v = {}", v);
+
+        let (u, v) = uv(225.0, 20.0);
+        let expected = 10.289 * std::f64::consts::FRAC_1_SQRT_2;
+        assert!((u - expected).abs() < 0.001, "u = {}", u);
+        assert!((v - expected).abs() < 0.001, "v = {}", v);
+
+        // Calm winds don't have a direction.
+        assert_eq!(uv(123.0, 0.0), (0.0, 0.0));
+
+        assert!(SurfaceData::default().wind_uv_mps().is_none());
+    }
+
+    #[test]
+    fn test_precip_type() {
+        use sounding_analysis::PrecipType::{
+            Hail, HeavyRain, HeavySnow, LightDrizzle, LightFreezingRain, LightIcePellets,
+            LightRain, LightRainAndSnow, LightSnow, ModerateRain, ModerateSnow,
+            ModerateSnowShowers, SnowGrains,
+        };
+
+        let flags = |rain, snow, fzra, ip| SurfaceData {
+            rain_type: rain,
+            snow_type: snow,
+            fzra_type: fzra,
+            ice_pellets_type: ip,
+            ..SurfaceData::default()
+        };
+
+        // No flags, or none of them set.
+        assert_eq!(flags(None, None, None, None).precip_type(), None);
+        let no = Some(false);
+        assert_eq!(flags(no, no, no, no).precip_type(), None);
+
+        // One flag set
+        let yes = Some(true);
+        assert_eq!(flags(yes, no, no, no).precip_type(), Some(LightRain));
+        assert_eq!(flags(no, yes, no, no).precip_type(), Some(LightSnow));
+        assert_eq!(
+            flags(no, no, yes, no).precip_type(),
+            Some(LightFreezingRain)
+        );
+        assert_eq!(flags(no, no, no, yes).precip_type(), Some(LightIcePellets));
+        assert_eq!(flags(None, yes, None, None).precip_type(), Some(LightSnow));
+
+        // Conflicting flags
+        assert_eq!(
+            flags(yes, yes, no, no).precip_type(),
+            Some(LightRainAndSnow)
+        );
+        assert_eq!(
+            flags(yes, no, yes, no).precip_type(),
+            Some(LightFreezingRain)
+        );
+        assert_eq!(flags(no, yes, no, yes).precip_type(), Some(LightIcePellets));
+        assert_eq!(
+            flags(yes, yes, yes, yes).precip_type(),
+            Some(LightFreezingRain)
+        );
+        assert_eq!(
+            flags(yes, yes, no, yes).precip_type(),
+            Some(LightIcePellets)
+        );
+
+        // The weather symbol code wins over the flags.
+        let sd = SurfaceData {
+            wx_sym_cod: some(70.0),
+            ..flags(yes, no, no, no)
+        };
+        assert_eq!(sd.precip_type(), Some(LightSnow));
+
+        // More codes from the WMO table
+        let code = |code: f64| {
+            SurfaceData {
+                wx_sym_cod: some(code),
+                ..SurfaceData::default()
+            }
+            .precip_type()
+        };
+        assert_eq!(code(51.0), Some(LightDrizzle));
+        assert_eq!(code(63.0), Some(ModerateRain));
+        assert_eq!(code(65.0), Some(HeavyRain));
+        assert_eq!(code(73.0), Some(ModerateSnow));
+        assert_eq!(code(75.0), Some(HeavySnow));
+        assert_eq!(code(77.0), Some(SnowGrains));
+        assert_eq!(code(86.0), Some(ModerateSnowShowers));
+        assert_eq!(code(96.0), Some(Hail));
+        assert_eq!(code(45.0), None);
+
+        // Unless it isn't a code that is recognized.
+        let sd = SurfaceData {
+            wx_sym_cod: some(1.0),
+            ..flags(yes, no, no, no)
+        };
+        assert_eq!(sd.precip_type(), Some(LightRain));
+    }
+
+    #[test]
+    fn test_display() {
+        let sd = SurfaceData {
+            station_num: 727730,
+            valid_time: NaiveDate::from_ymd_opt(2017, 4, 1)
+                .and_then(|d| d.and_hms_opt(3, 0, 0))
+                .unwrap(),
+            mslp: some(HectoPascal(1021.5)),
+            wind: some(WindSpdDir {
+                speed: Knots(2.6),
+                direction: 49.0,
+            }),
+            temperature: some(Celsius(3.24)),
+            dewpoint: some(Celsius(0.62)),
+            ..SurfaceData::default()
+        };
+        assert_eq!(
+            sd.to_string(),
+            "2017-04-01 03:00Z MSLP 1021.5 hPa, T 3.2 C, Td 0.6 C, wind 049/3 kt"
+        );
+
+        let sd = SurfaceData {
+            mslp: none(),
+            wind: none(),
+            ..sd
+        };
+        assert_eq!(
+            sd.to_string(),
+            "2017-04-01 03:00Z MSLP --, T 3.2 C, Td 0.6 C, wind --"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_round_trip() {
+        let sd = SurfaceData {
+            station_num: 727730,
+            valid_time: NaiveDate::from_ymd_opt(2017, 4, 1)
+                .and_then(|d| d.and_hms_opt(3, 0, 0))
+                .unwrap(),
+            mslp: some(HectoPascal(1021.5)),
+            station_pres: some(HectoPascal(909.4)),
+            low_cloud: some(0.52),
+            mid_cloud: some(0.0),
+            hi_cloud: some(0.61),
+            wind: some(WindSpdDir {
+                speed: Knots(2.6),
+                direction: 249.0,
+            }),
+            temperature: some(Celsius(3.24)),
+            dewpoint: some(Celsius(0.62)),
+            skin_temp: some(Celsius(2.14)),
+            lyr_1_soil_temp: some(Kelvin(278.2)),
+            snow_1hr: some(0.0),
+            p01: some(Mm(0.1)),
+            c01: some(Mm(0.0)),
+            lyr_2_soil_temp: some(Kelvin(277.1)),
+            snow_ratio: some(0.0),
+            snow_type: Some(false),
+            ice_pellets_type: Some(false),
+            fzra_type: Some(false),
+            rain_type: Some(true),
+            storm_motion: some(WindUV {
+                u: MetersPSec(1.3),
+                v: MetersPSec(0.5),
+            }),
+            srh: some(12.0),
+            wx_sym_cod: some(60.0),
+            cloud_base_pres: some(HectoPascal(801.2)),
+            visibility: some(Km(16.0)),
+        };
+
+        let json = serde_json::to_string(&sd).unwrap();
+        let back: SurfaceData = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, sd);
+
+        // Missing values survive the round trip as missing.
+        let sd = SurfaceData {
+            mslp: none(),
+            wind: none(),
+            storm_motion: none(),
+            rain_type: None,
+            ..sd
+        };
+
+        let json = serde_json::to_string(&sd).unwrap();
+        let back: SurfaceData = serde_json::from_str(&json).unwrap();
+        assert!(back.mslp.is_none());
+        assert!(back.wind.is_none());
+        assert!(back.storm_motion.is_none());
+        assert!(back.rain_type.is_none());
+        assert_eq!(back.station_pres, sd.station_pres);
+        assert_eq!(back.valid_time, sd.valid_time);
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+    }
 }
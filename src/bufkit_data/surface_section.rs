@@ -2,24 +2,61 @@
 
 use crate::bufkit_data::surface::{SfcColumns, SurfaceData};
 use crate::error::*;
-use std::error::Error;
+use crate::parse_util::ParseOptions;
+use chrono::NaiveDateTime;
 
 /// Represents the section of a string that represents surface data in a bufkit file.
+///
+/// This is useful for parsing just the surface data, e.g. from a snippet of a file.
+///
+/// ```
+/// use sounding_bufkit::SurfaceSection;
+///
+/// let text = "STN YYMMDD/HHMM PMSL PRES SKTC STC1 SNFL WTNS
+///             P01M C01M STC2 LCLD MCLD HCLD
+///             SNRA UWND VWND R01M BFGR T2MS
+///             Q2MS WXTS WXTP WXTZ WXTR USTM
+///             VSTM HLCY SLLH WSYM CDBP VSBK
+///             TD2M
+///             727730 170401/0000 1017.40 869.60 10.64 284.90 -9999.00 74.00
+///             -9999.00 -9999.00 277.20 0.00 0.00 1.00
+///             0.00 0.40 -0.10 -9999.00 -9999.00 8.84
+///             4.76 0.00 0.00 0.00 0.00 -7.10
+///             -8.50 4.00 -9999.00 999.00 -9999.00 20.00
+///             1.02";
+///
+/// let section = SurfaceSection::new(text).unwrap();
+/// let data = section.parse_all().unwrap();
+/// assert_eq!(data.len(), 1);
+/// assert_eq!(data[0].station_num, 727730);
+/// ```
 pub struct SurfaceSection<'a> {
+    text: &'a str,
     raw_text: &'a str,
     columns: SfcColumns,
+    options: ParseOptions,
 }
 
 impl<'a> SurfaceSection<'a> {
     /// Initialize a surface section for parsing.
-    pub fn init(text: &'a str) -> Result<SurfaceSection<'a>, BufkitFileError> {
+    ///
+    /// The text must start with the header of column names, followed by the values.
+    pub fn new(text: &'a str) -> Result<SurfaceSection<'a>, BufkitFileError> {
+        SurfaceSection::with_options(text, ParseOptions::default())
+    }
+
+    /// Initialize a surface section for parsing with `options`.
+    pub fn with_options(
+        text: &'a str,
+        options: ParseOptions,
+    ) -> Result<SurfaceSection<'a>, BufkitFileError> {
         // Split the header off
         let mut header_end: usize = 0;
         let mut previous_char = 'x';
         let mut found = false;
         for (i, c) in text.char_indices() {
             header_end = i;
-            if previous_char.is_whitespace() && c.is_digit(10) {
+            if previous_char.is_whitespace() && c.is_ascii_digit() {
                 found = true;
                 break;
             } else {
@@ -27,7 +64,7 @@ impl<'a> SurfaceSection<'a> {
             }
         }
         if !found {
-            return Err(BufkitFileError::new());
+            return Err(BufkitFileError::MissingSection("surface data".to_owned()));
         }
         let header = &text[0..header_end].trim();
 
@@ -35,25 +72,181 @@ impl<'a> SurfaceSection<'a> {
         let cols = SurfaceData::parse_columns(header)?;
 
         Ok(SurfaceSection {
-            raw_text: text[header_end..].trim(),
+            text,
+            raw_text: trim_trailer(text[header_end..].trim()),
             columns: cols,
+            options,
         })
     }
 
+    /// Get the text of the whole section, including the header.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Get the columns parsed from the header.
+    pub fn columns(&self) -> &SfcColumns {
+        &self.columns
+    }
+
     /// Validate the surface section of a sounding.
-    pub fn validate_section(&self) -> Result<(), Box<dyn Error>> {
+    ///
+    /// Errors have the line number where they happened, relative to the start of the section.
+    pub fn validate_section(&self) -> Result<(), BufkitFileError> {
+        self.parse_each(|_| {})?;
+        self.validate_times()
+    }
+
+    /// Check that the valid times are strictly increasing, with no duplicates.
+    ///
+    /// The error has the line number of the first record out of order, relative to the start of
+    /// the section.
+    pub fn validate_times(&self) -> Result<(), BufkitFileError> {
+        use crate::parse_util::line_of;
+
+        let mut iter = self.into_iter();
+        let mut last_time: Option<NaiveDateTime> = None;
+
+        while let Some(chunk) = iter.next_usable_chunk() {
+            if let Ok(vt) = SurfaceData::parse_valid_time(chunk, iter.columns) {
+                if last_time.is_some_and(|last| vt <= last) {
+                    return Err(BufkitFileError::TimeNotIncreasing(vt)
+                        .located(line_of(self.text, chunk.trim_start())));
+                }
+                last_time = Some(vt);
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse all of the surface data, failing on the first bad record like `validate_section`.
+    pub fn parse_all(&self) -> Result<Vec<SurfaceData>, BufkitFileError> {
+        let mut all = vec![];
+        self.parse_each(|sd| all.push(sd))?;
+        self.validate_times()?;
+        Ok(all)
+    }
+
+    /// Parse all of the surface data in parallel, skipping any records that fail like the
+    /// iterator does.
+    #[cfg(feature = "rayon")]
+    pub fn par_parse(&self) -> Vec<SurfaceData> {
+        use rayon::prelude::*;
+
+        let mut iter = self.into_iter();
+        let chunks: Vec<&str> = std::iter::from_fn(|| iter.next_usable_chunk()).collect();
+
+        chunks
+            .into_par_iter()
+            .filter_map(|chunk| SurfaceData::parse_values(chunk, &self.columns, &self.options).ok())
+            .collect()
+    }
+
+    fn parse_each<F: FnMut(SurfaceData)>(&self, mut f: F) -> Result<(), BufkitFileError> {
+        use crate::parse_util::line_of;
+
         let mut iter = self.into_iter();
 
         loop {
-            let opt = iter.get_next_chunk()?;
+            let start_line = line_of(self.text, iter.remaining.trim_start());
+            let opt = iter
+                .get_next_chunk()
+                .map_err(|err| err.located(start_line))?;
             if let Some(chunk) = opt {
-                SurfaceData::parse_values(chunk, iter.columns)?;
+                let sd = SurfaceData::parse_values(chunk, iter.columns, iter.options)
+                    .map_err(|err| err.located(line_of(self.text, chunk)))?;
+                f(sd);
             } else {
                 break;
             }
         }
         Ok(())
     }
+
+    /// Validate every record in the surface section, collecting all the errors instead of
+    /// stopping at the first one.
+    ///
+    /// Each error is paired with the line where its record starts, which is often not the line of
+    /// the bad value in the error. Both are relative to the start of the section.
+    pub fn validate_collect(&self) -> Vec<(usize, BufkitFileError)> {
+        use crate::parse_util::line_of;
+
+        let mut errors = vec![];
+        let mut iter = self.into_iter();
+
+        loop {
+            let start_line = line_of(self.text, iter.remaining.trim_start());
+            let remaining_len = iter.remaining.len();
+            match iter.get_next_chunk() {
+                Ok(Some(chunk)) => {
+                    if let Err(err) = SurfaceData::parse_values(chunk, iter.columns, iter.options) {
+                        errors.push((start_line, err.located(line_of(self.text, chunk))));
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    errors.push((start_line, err.located(start_line)));
+                    // Nothing left to resync to.
+                    if iter.remaining.len() == remaining_len {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let Err(err) = self.validate_times() {
+            let line = err.line().unwrap_or(1);
+            errors.push((line, err));
+            errors.sort_by_key(|&(line, _)| line);
+        }
+
+        errors
+    }
+
+    /// Iterate over the valid times of the surface data without parsing the other values.
+    pub fn valid_times(&'a self) -> impl Iterator<Item = NaiveDateTime> + 'a {
+        let mut iter = self.into_iter();
+
+        std::iter::from_fn(move || {
+            while let Some(chunk) = iter.next_usable_chunk() {
+                if let Ok(vt) = SurfaceData::parse_valid_time(chunk, iter.columns) {
+                    return Some(vt);
+                }
+            }
+            None
+        })
+    }
+
+    /// Find and parse the surface data with the given valid time, without parsing the others.
+    pub fn parse_at(&'a self, valid_time: NaiveDateTime) -> Option<SurfaceData> {
+        let mut iter = self.into_iter();
+
+        while let Some(chunk) = iter.next_usable_chunk() {
+            if SurfaceData::parse_valid_time(chunk, iter.columns).ok() == Some(valid_time) {
+                return SurfaceData::parse_values(chunk, iter.columns, iter.options).ok();
+            }
+        }
+        None
+    }
+}
+
+/// Remove any lines at the end of the section that are obviously not data, e.g. a comment added
+/// by whatever produced the file.
+///
+/// Every line of a record starts with a number, so trailing lines that don't are dropped. Lines
+/// like that in the middle of the section are left alone, so they are still errors.
+fn trim_trailer(text: &str) -> &str {
+    let mut text = text.trim_end();
+    loop {
+        let start = text.rfind('\n').map_or(0, |i| i + 1);
+        let is_data = text[start..]
+            .trim_start()
+            .starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.' || c == '+');
+        if is_data || start == 0 {
+            return text;
+        }
+        text = text[..start].trim_end();
+    }
 }
 
 impl<'a> IntoIterator for &'a SurfaceSection<'a> {
@@ -64,16 +257,19 @@ impl<'a> IntoIterator for &'a SurfaceSection<'a> {
         SurfaceIterator {
             remaining: self.raw_text,
             columns: &self.columns,
+            options: &self.options,
         }
     }
 }
 
 /// Iterator struct that parses one entry at a time.
 ///
-/// If there is a parsing error, it skips the entry that caused it and moves on.
+/// If there is a parsing error, it skips the entry that caused it and moves on. A record with a
+/// missing or extra value is skipped too, and iteration picks up again at the next record.
 pub struct SurfaceIterator<'a> {
     remaining: &'a str,
     columns: &'a SfcColumns,
+    options: &'a ParseOptions,
 }
 
 impl<'a> SurfaceIterator<'a> {
@@ -81,20 +277,130 @@ impl<'a> SurfaceIterator<'a> {
         use crate::parse_util::find_next_n_tokens;
         if let Some(brk) = find_next_n_tokens(self.remaining, self.columns.num_cols())? {
             let next_chunk = &self.remaining[0..brk];
-            self.remaining = &self.remaining[brk..];
+            let rest = &self.remaining[brk..];
+
+            // A record with a missing or extra value still splits into the right number of tokens,
+            // but the tokens are shifted into the neighboring record. Skip ahead to the next place
+            // that looks like the start of a record so the rest of the section is still usable.
+            let aligned = self.columns.starts_with_record(next_chunk)
+                && (rest.trim().is_empty() || self.columns.starts_with_record(rest));
+            if !aligned {
+                self.remaining = match self.columns.find_next_record_start(self.remaining) {
+                    Some(start) => &self.remaining[start..],
+                    None => "",
+                };
+                return Err(BufkitFileError::MisalignedRecord);
+            }
+
+            self.remaining = rest;
+            Ok(Some(next_chunk))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Get the next chunk, skipping past misaligned records instead of stopping at them.
+    fn next_usable_chunk(&mut self) -> Option<&'a str> {
+        loop {
+            let remaining_len = self.remaining.len();
+            match self.get_next_chunk() {
+                Ok(opt) => return opt,
+                // Skipped a misaligned record, try again from the next record.
+                Err(_) if self.remaining.len() < remaining_len => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+
+    /// Get the text of the next surface record along with its valid time, without parsing the
+    /// rest of it.
+    ///
+    /// Records without a valid time that parses are skipped.
+    pub(crate) fn next_timed_chunk(&mut self) -> Option<(NaiveDateTime, &'a str)> {
+        while let Some(chunk) = self.next_usable_chunk() {
+            if let Ok(valid_time) = SurfaceData::parse_valid_time(chunk, self.columns) {
+                return Some((valid_time, chunk));
+            }
+        }
+        None
+    }
+
+    /// Parse the text of a surface record from `next_timed_chunk`.
+    pub(crate) fn parse_chunk(&self, chunk: &str) -> Option<SurfaceData> {
+        SurfaceData::parse_values(chunk, self.columns, self.options).ok()
+    }
+
+    /// Get the valid time of the next surface record without consuming it.
+    ///
+    /// Only the valid time is parsed, so if the rest of the next record fails to parse, `next`
+    /// will skip it and return a later record.
+    pub fn peek_time(&self) -> Option<NaiveDateTime> {
+        let mut iter = SurfaceIterator {
+            remaining: self.remaining,
+            columns: self.columns,
+            options: self.options,
+        };
+        while let Some(chunk) = iter.next_usable_chunk() {
+            if let Ok(valid_time) = SurfaceData::parse_valid_time(chunk, self.columns) {
+                return Some(valid_time);
+            }
+        }
+        None
+    }
+
+    fn get_next_back_chunk(&mut self) -> Result<Option<&'a str>, BufkitFileError> {
+        use crate::parse_util::find_last_n_tokens;
+        if let Some(brk) = find_last_n_tokens(self.remaining, self.columns.num_cols())? {
+            let next_chunk = &self.remaining[brk..];
+
+            // Same as get_next_chunk, but skip back to the previous start of a record instead.
+            if !self.columns.starts_with_record(next_chunk) {
+                self.remaining = match self.columns.find_last_record_start(self.remaining) {
+                    Some(start) => &self.remaining[0..start],
+                    None => "",
+                };
+                return Err(BufkitFileError::MisalignedRecord);
+            }
+
+            self.remaining = &self.remaining[0..brk];
             Ok(Some(next_chunk))
         } else {
             Ok(None)
         }
     }
+
+    /// Get the next chunk from the back, skipping past misaligned records instead of stopping
+    /// at them.
+    fn next_usable_back_chunk(&mut self) -> Option<&'a str> {
+        loop {
+            let remaining_len = self.remaining.len();
+            match self.get_next_back_chunk() {
+                Ok(opt) => return opt,
+                Err(_) if self.remaining.len() < remaining_len => continue,
+                Err(_) => return None,
+            }
+        }
+    }
 }
 
 impl<'a> Iterator for SurfaceIterator<'a> {
     type Item = SurfaceData;
 
     fn next(&mut self) -> Option<SurfaceData> {
-        while let Ok(Some(text)) = self.get_next_chunk() {
-            if let Ok(sd) = SurfaceData::parse_values(text, self.columns) {
+        while let Some(text) = self.next_usable_chunk() {
+            if let Ok(sd) = SurfaceData::parse_values(text, self.columns, self.options) {
+                return Some(sd);
+            }
+        }
+        // Ran out of text to try.
+        None
+    }
+}
+
+impl<'a> DoubleEndedIterator for SurfaceIterator<'a> {
+    fn next_back(&mut self) -> Option<SurfaceData> {
+        while let Some(text) = self.next_usable_back_chunk() {
+            if let Ok(sd) = SurfaceData::parse_values(text, self.columns, self.options) {
                 return Some(sd);
             }
         }
@@ -106,6 +412,7 @@ impl<'a> Iterator for SurfaceIterator<'a> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use chrono::NaiveDate;
 
     fn get_valid_test_data() -> &'static str {
         "
@@ -213,7 +520,7 @@ mod test {
 
         let test_data = get_valid_test_data();
 
-        let surface_section = SurfaceSection::init(test_data).unwrap();
+        let surface_section = SurfaceSection::new(test_data).unwrap();
 
         assert_eq!(surface_section.into_iter().count(), 6);
 
@@ -227,12 +534,30 @@ mod test {
                 .map(|sd| sd.valid_time)
                 .collect::<Vec<NaiveDateTime>>(),
             vec![
-                NaiveDate::from_ymd(2017, 4, 1).and_hms(0, 0, 0),
-                NaiveDate::from_ymd(2017, 4, 1).and_hms(3, 0, 0),
-                NaiveDate::from_ymd(2017, 4, 1).and_hms(6, 0, 0),
-                NaiveDate::from_ymd(2017, 4, 1).and_hms(9, 0, 0),
-                NaiveDate::from_ymd(2017, 4, 1).and_hms(12, 0, 0),
-                NaiveDate::from_ymd(2017, 4, 1).and_hms(15, 0, 0),
+                NaiveDate::from_ymd_opt(2017, 4, 1)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap(),
+                NaiveDate::from_ymd_opt(2017, 4, 1)
+                    .unwrap()
+                    .and_hms_opt(3, 0, 0)
+                    .unwrap(),
+                NaiveDate::from_ymd_opt(2017, 4, 1)
+                    .unwrap()
+                    .and_hms_opt(6, 0, 0)
+                    .unwrap(),
+                NaiveDate::from_ymd_opt(2017, 4, 1)
+                    .unwrap()
+                    .and_hms_opt(9, 0, 0)
+                    .unwrap(),
+                NaiveDate::from_ymd_opt(2017, 4, 1)
+                    .unwrap()
+                    .and_hms_opt(12, 0, 0)
+                    .unwrap(),
+                NaiveDate::from_ymd_opt(2017, 4, 1)
+                    .unwrap()
+                    .and_hms_opt(15, 0, 0)
+                    .unwrap(),
             ]
         );
 
@@ -333,16 +658,208 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_valid_times() {
+        let surface_section = SurfaceSection::new(get_valid_test_data()).unwrap();
+
+        assert_eq!(
+            surface_section.valid_times().collect::<Vec<_>>(),
+            surface_section
+                .into_iter()
+                .map(|sd| sd.valid_time)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_surface_iterator_reversed() {
+        let surface_section = SurfaceSection::new(get_valid_test_data()).unwrap();
+
+        let mut forward: Vec<_> = surface_section
+            .into_iter()
+            .map(|sd| (sd.valid_time, sd.mslp))
+            .collect();
+        forward.reverse();
+        let backward: Vec<_> = surface_section
+            .into_iter()
+            .rev()
+            .map(|sd| (sd.valid_time, sd.mslp))
+            .collect();
+
+        assert_eq!(forward.len(), 6);
+        assert_eq!(forward, backward);
+
+        // Meet in the middle.
+        let mut iter = surface_section.into_iter();
+        let first = iter.next().unwrap();
+        let last = iter.next_back().unwrap();
+        assert!(first.valid_time < last.valid_time);
+        assert_eq!(iter.count(), 4);
+
+        // A record with a missing or an extra value is skipped in both directions.
+        for text in &[
+            get_valid_test_data().replacen("909.40 2.14 278.20", "909.40 278.20", 1),
+            get_valid_test_data().replacen("909.40 2.14", "909.40 2.14 2.14", 1),
+        ] {
+            let surface_section = SurfaceSection::new(text).unwrap();
+
+            let mut forward: Vec<_> = surface_section
+                .into_iter()
+                .map(|sd| sd.valid_time.format("%H%M").to_string())
+                .collect();
+            assert_eq!(forward, vec!["0000", "0600", "0900", "1200", "1500"]);
+
+            forward.reverse();
+            let backward: Vec<_> = surface_section
+                .into_iter()
+                .rev()
+                .map(|sd| sd.valid_time.format("%H%M").to_string())
+                .collect();
+            assert_eq!(forward, backward);
+        }
+    }
+
+    #[test]
+    fn test_misaligned_record_skipped() {
+        // Drop the SKTC value from the 0300 record, so the 0600 station number gets pulled into it.
+        let text = get_valid_test_data().replacen("909.40 2.14 278.20", "909.40 278.20", 1);
+        let surface_section = SurfaceSection::new(&text).unwrap();
+
+        let times: Vec<_> = surface_section
+            .into_iter()
+            .map(|sd| sd.valid_time.format("%H%M").to_string())
+            .collect();
+        assert_eq!(times, vec!["0000", "0600", "0900", "1200", "1500"]);
+
+        assert_eq!(surface_section.valid_times().count(), 5);
+        assert!(surface_section.validate_section().is_err());
+
+        // An extra value shifts the next record the other way.
+        let text = get_valid_test_data().replacen("909.40 2.14", "909.40 2.14 2.14", 1);
+        let surface_section = SurfaceSection::new(&text).unwrap();
+        assert_eq!(surface_section.into_iter().count(), 5);
+        assert!(surface_section.validate_section().is_err());
+    }
+
+    #[test]
+    fn test_validate_line_number() {
+        // The 0900 record starts on line 18, the first line is empty.
+        let text = get_valid_test_data().replacen("1022.70", "10x2.70", 1);
+        let surface_section = SurfaceSection::new(&text).unwrap();
+
+        let err = surface_section.validate_section().unwrap_err();
+        assert_eq!(
+            err,
+            BufkitFileError::AtLine {
+                line: 18,
+                error: Box::new(BufkitFileError::ParseFloat("10x2.70".to_owned())),
+            }
+        );
+
+        // A value on the last line of the 0900 record.
+        let text = get_valid_test_data().replacen("-1.99", "-1.x99", 1);
+        let surface_section = SurfaceSection::new(&text).unwrap();
+        let err = surface_section.validate_section().unwrap_err();
+        assert_eq!(err.line(), Some(21));
+    }
+
+    #[test]
+    fn test_validate_times() {
+        let surface_section = SurfaceSection::new(get_valid_test_data()).unwrap();
+        assert!(surface_section.validate_times().is_ok());
+
+        // Duplicate the 0300 time in the 0600 record, which starts on line 14.
+        let text = get_valid_test_data().replacen("170401/0600", "170401/0300", 1);
+        let surface_section = SurfaceSection::new(&text).unwrap();
+        let dup = NaiveDate::from_ymd_opt(2017, 4, 1)
+            .and_then(|d| d.and_hms_opt(3, 0, 0))
+            .unwrap();
+        let expected = BufkitFileError::TimeNotIncreasing(dup).located(14);
+        assert_eq!(surface_section.validate_times(), Err(expected.clone()));
+        assert_eq!(surface_section.validate_section(), Err(expected));
+    }
+
+    #[test]
+    fn test_crlf() {
+        let lf = SurfaceSection::new(get_valid_test_data()).unwrap();
+        let crlf_text = get_valid_test_data().replace('\n', "\r\n");
+        let crlf = SurfaceSection::new(&crlf_text).unwrap();
+
+        assert!(crlf.validate_section().is_ok());
+        // Missing values are NaN, so compare the Debug output instead of the values.
+        let lf: Vec<String> = lf.into_iter().map(|sd| format!("{:?}", sd)).collect();
+        let crlf: Vec<String> = crlf.into_iter().map(|sd| format!("{:?}", sd)).collect();
+        assert_eq!(lf.len(), 6);
+        assert_eq!(lf, crlf);
+    }
+
+    #[test]
+    fn test_trailing_garbage() {
+        for trailer in &[
+            "\n# Generated by a bufkit exporter\n",
+            "\nEND\n\n",
+            "\n   \n",
+        ] {
+            let text = format!("{}{}", get_valid_test_data(), trailer);
+            let surface_section = SurfaceSection::new(&text).unwrap();
+
+            assert!(surface_section.validate_section().is_ok());
+            assert!(surface_section.validate_collect().is_empty());
+            assert_eq!(surface_section.into_iter().count(), 6);
+            assert_eq!(surface_section.into_iter().rev().count(), 6);
+        }
+
+        // Garbage before the last record is still an error.
+        let text = get_valid_test_data().replacen(
+            "        727730 170401/1500",
+            "        # Not data\n        727730 170401/1500",
+            1,
+        );
+        let surface_section = SurfaceSection::new(&text).unwrap();
+        assert!(surface_section.validate_section().is_err());
+    }
+
     #[test]
     fn test_validate() {
-        let surface_section = SurfaceSection::init(get_valid_test_data()).unwrap();
+        let surface_section = SurfaceSection::new(get_valid_test_data()).unwrap();
         assert!(surface_section.validate_section().is_ok());
 
         println!("DOING TEST 1");
-        let surface_section = SurfaceSection::init(get_invalid_test_data1()).unwrap();
-        assert!(!surface_section.validate_section().is_ok());
+        let surface_section = SurfaceSection::new(get_invalid_test_data1()).unwrap();
+        assert!(surface_section.validate_section().is_err());
         println!("DONE TEST 1");
 
-        assert!(SurfaceSection::init(get_invalid_test_data2()).is_err());
+        assert!(SurfaceSection::new(get_invalid_test_data2()).is_err());
+    }
+
+    #[test]
+    fn test_peek_time() {
+        let surface_section = SurfaceSection::new(get_valid_test_data()).unwrap();
+        let mut iter = surface_section.into_iter();
+
+        let mut count = 0;
+        while let Some(valid_time) = iter.peek_time() {
+            // Peeking doesn't advance the iterator.
+            assert_eq!(iter.peek_time(), Some(valid_time));
+            assert_eq!(iter.next().map(|sd| sd.valid_time), Some(valid_time));
+            count += 1;
+        }
+        assert_eq!(count, 6);
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_columns() {
+        use crate::bufkit_data::surface::SfcColName::*;
+
+        let surface_section = SurfaceSection::new(get_valid_test_data()).unwrap();
+
+        assert_eq!(
+            surface_section.columns().column_names(),
+            &[
+                STN, VALIDTIME, PMSL, PRES, SKTC, STC1, NONE, NONE, NONE, NONE, LCLD, MCLD, HCLD,
+                UWND, VWND, T2MS, NONE, WXTS, WXTP, WXTZ, WXTR, NONE, TD2M
+            ]
+        );
     }
 }
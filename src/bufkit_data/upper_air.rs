@@ -1,80 +1,329 @@
 //! Module for parsing the upper air section of a bufkit file.
 
+mod builder;
 mod indexes;
+mod interpolation;
 mod profile;
 mod station_info;
 
+pub use self::builder::UpperAirBuilder;
 use crate::error::*;
+use crate::parse_util::{check_missing_i32, ParseOptions};
 use chrono::NaiveDateTime;
 use metfor::{
     Celsius, CelsiusDiff, HectoPascal, JpKg, Kelvin, Knots, Meters, Mm, PaPS, WindSpdDir,
 };
 use optional::Optioned;
-use std::error::Error;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The latitude, longitude, and elevation of a station.
+pub(crate) type Location = (Optioned<f64>, Optioned<f64>, Optioned<Meters>);
+
+/// A mandatory pressure level with the temperature, dew point, and wind at that level.
+pub(crate) type MandatoryLevel = (
+    HectoPascal,
+    Optioned<Celsius>,
+    Optioned<Celsius>,
+    Optioned<WindSpdDir<Knots>>,
+);
+
+/// Scratch space and values shared between soundings, reused while parsing a whole section.
+#[derive(Debug, Default)]
+pub(crate) struct ParseBuffer {
+    profile: profile::ProfileBuffer,
+    station_id: Option<Arc<str>>,
+    options: ParseOptions,
+}
+
+impl ParseBuffer {
+    /// Create an empty buffer for parsing with `options`.
+    pub(crate) fn new(options: ParseOptions) -> Self {
+        ParseBuffer {
+            options,
+            ..ParseBuffer::default()
+        }
+    }
+
+    /// Get a shared copy of the station id, only allocating if it changed since the last one.
+    fn shared_station_id(&mut self, id: &str) -> Arc<str> {
+        match &self.station_id {
+            Some(shared) if **shared == *id => Arc::clone(shared),
+            _ => {
+                let shared: Arc<str> = Arc::from(id);
+                self.station_id = Some(Arc::clone(&shared));
+                shared
+            }
+        }
+    }
+}
+
+/// The profiles in an `UpperAir`, used to pick which ones to check or use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProfileField {
+    /// Pressure
+    Pressure,
+    /// Temperature
+    Temperature,
+    /// Wet bulb temperature
+    WetBulb,
+    /// Dew point
+    DewPoint,
+    /// Equivalent potential temperature
+    ThetaE,
+    /// Wind speed and direction
+    Wind,
+    /// Pressure vertical velocity
+    Omega,
+    /// Height above MSL
+    Height,
+    /// Cloud fraction
+    CloudFraction,
+    /// Mixing ratio
+    MixingRatio,
+}
+
+impl std::fmt::Display for ProfileField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ProfileField::*;
+
+        let name = match self {
+            Pressure => "pressure",
+            Temperature => "temperature",
+            WetBulb => "wet bulb",
+            DewPoint => "dew point",
+            ThetaE => "theta-e",
+            Wind => "wind",
+            Omega => "omega",
+            Height => "height",
+            CloudFraction => "cloud fraction",
+            MixingRatio => "mixing ratio",
+        };
+        write!(f, "{}", name)
+    }
+}
 
 /// All the values from a parsed sounding in one struct.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UpperAir {
     // Station info
-    pub num: i32,                    // station number, USAF number, eg 727730
-    pub valid_time: NaiveDateTime,   // valid time of sounding
-    pub lead_time: i32,              // Forecast lead time in hours from model init
-    pub id: Option<String>,          // Usually a 3 or 4 letter alpha numeric designation.
-    pub lat: Optioned<f64>,          // latitude
-    pub lon: Optioned<f64>,          // longitude
-    pub elevation: Optioned<Meters>, // elevation (m)
+    /// Station number, USAF number, eg 727730, or -9999 if it was blank in the file
+    pub num: i32,
+    /// Valid time of sounding
+    pub valid_time: NaiveDateTime,
+    /// Forecast lead time in hours from model init
+    pub lead_time: i32,
+    /// Usually a 3 or 4 letter alpha numeric designation.
+    ///
+    /// Soundings parsed from the same file share a single copy of the id.
+    pub id: Option<Arc<str>>,
+    /// Latitude
+    pub lat: Optioned<f64>,
+    /// Longitude
+    pub lon: Optioned<f64>,
+    /// Elevation
+    pub elevation: Optioned<Meters>,
 
     // Indexes
-    pub show: Optioned<CelsiusDiff>, // Showalter index
-    pub li: Optioned<CelsiusDiff>,   // Lifted index
-    pub swet: Optioned<f64>,         // Severe Weather Threat index
-    pub kinx: Optioned<Celsius>,     // K-index
-    pub lclp: Optioned<HectoPascal>, // Lifting Condensation Level (hPa)
-    pub pwat: Optioned<Mm>,          // Precipitable water (mm)
-    pub totl: Optioned<f64>,         // Total-Totals
-    pub cape: Optioned<JpKg>,        // Convective Available Potential Energy
-    pub lclt: Optioned<Kelvin>,      // Temperature at LCL (K)
-    pub cins: Optioned<JpKg>,        // Convective Inhibitive Energy
-    pub eqlv: Optioned<HectoPascal>, // Equilibrium Level (hPa)
-    pub lfc: Optioned<HectoPascal>,  // Level of Free Convection (hPa)
-    pub brch: Optioned<f64>,         // Bulk Richardson Number
+    /// Showalter index
+    pub show: Optioned<CelsiusDiff>,
+    /// Lifted index
+    pub li: Optioned<CelsiusDiff>,
+    /// Severe Weather Threat index
+    pub swet: Optioned<f64>,
+    /// K-index
+    pub kinx: Optioned<Celsius>,
+    /// Lifting Condensation Level
+    pub lclp: Optioned<HectoPascal>,
+    /// Precipitable water
+    pub pwat: Optioned<Mm>,
+    /// Total-Totals
+    pub totl: Optioned<f64>,
+    /// Convective Available Potential Energy
+    pub cape: Optioned<JpKg>,
+    /// Temperature at LCL
+    pub lclt: Optioned<Kelvin>,
+    /// Convective Inhibitive Energy
+    pub cins: Optioned<JpKg>,
+    /// Equilibrium Level
+    pub eqlv: Optioned<HectoPascal>,
+    /// Level of Free Convection
+    pub lfc: Optioned<HectoPascal>,
+    /// Bulk Richardson Number
+    pub brch: Optioned<f64>,
 
     // Upper air
-    pub pressure: Vec<Optioned<HectoPascal>>, // Pressure (hPa)
-    pub temperature: Vec<Optioned<Celsius>>,  // Temperature (C)
-    pub wet_bulb: Vec<Optioned<Celsius>>,     // Wet Bulb (C)
-    pub dew_point: Vec<Optioned<Celsius>>,    // Dew Point (C)
-    pub theta_e: Vec<Optioned<Kelvin>>,       // Equivalent Potential Temperature (K)
-    pub wind: Vec<Optioned<WindSpdDir<Knots>>>, // Wind speed and direction, knots
-    pub omega: Vec<Optioned<PaPS>>,           // Pressure vertical velocity (Pa/sec)
-    pub height: Vec<Optioned<Meters>>,        // height above MSL in meters
-    pub cloud_fraction: Vec<Optioned<f64>>,   // Cloud fraction
+    /// Pressure
+    pub pressure: Vec<Optioned<HectoPascal>>,
+    /// Temperature
+    pub temperature: Vec<Optioned<Celsius>>,
+    /// Wet Bulb
+    pub wet_bulb: Vec<Optioned<Celsius>>,
+    /// Dew Point
+    pub dew_point: Vec<Optioned<Celsius>>,
+    /// Equivalent Potential Temperature
+    pub theta_e: Vec<Optioned<Kelvin>>,
+    /// Wind speed and direction
+    pub wind: Vec<Optioned<WindSpdDir<Knots>>>,
+    /// Pressure vertical velocity
+    pub omega: Vec<Optioned<PaPS>>,
+    /// Height above MSL
+    pub height: Vec<Optioned<Meters>>,
+    /// Cloud fraction from 0 to 1, the same as the surface cloud cover. The files have percent.
+    pub cloud_fraction: Vec<Optioned<f64>>,
+    /// Mixing ratio (g/kg), not in most files
+    pub mixing_ratio: Vec<Optioned<f64>>,
+}
+
+// Missing values are NaN for most of the metfor types, so a derived PartialEq would say two
+// soundings with a missing value at the same level are different.
+impl PartialEq for UpperAir {
+    fn eq(&self, other: &UpperAir) -> bool {
+        use optional::Noned;
+
+        fn same<T: Noned + Copy + PartialEq>(a: Optioned<T>, b: Optioned<T>) -> bool {
+            a.into_option() == b.into_option()
+        }
+
+        fn same_profile<T: Noned + Copy + PartialEq>(a: &[Optioned<T>], b: &[Optioned<T>]) -> bool {
+            a.len() == b.len() && a.iter().zip(b).all(|(&a, &b)| same(a, b))
+        }
+
+        self.num == other.num
+            && self.valid_time == other.valid_time
+            && self.lead_time == other.lead_time
+            && self.id == other.id
+            && same(self.lat, other.lat)
+            && same(self.lon, other.lon)
+            && same(self.elevation, other.elevation)
+            && same(self.show, other.show)
+            && same(self.li, other.li)
+            && same(self.swet, other.swet)
+            && same(self.kinx, other.kinx)
+            && same(self.lclp, other.lclp)
+            && same(self.pwat, other.pwat)
+            && same(self.totl, other.totl)
+            && same(self.cape, other.cape)
+            && same(self.lclt, other.lclt)
+            && same(self.cins, other.cins)
+            && same(self.eqlv, other.eqlv)
+            && same(self.lfc, other.lfc)
+            && same(self.brch, other.brch)
+            && same_profile(&self.pressure, &other.pressure)
+            && same_profile(&self.temperature, &other.temperature)
+            && same_profile(&self.wet_bulb, &other.wet_bulb)
+            && same_profile(&self.dew_point, &other.dew_point)
+            && same_profile(&self.theta_e, &other.theta_e)
+            && same_profile(&self.wind, &other.wind)
+            && same_profile(&self.omega, &other.omega)
+            && same_profile(&self.height, &other.height)
+            && same_profile(&self.cloud_fraction, &other.cloud_fraction)
+            && same_profile(&self.mixing_ratio, &other.mixing_ratio)
+    }
+}
+
+/// The values of every profile at a single level of an `UpperAir`.
+///
+/// Profiles that are empty in the sounding are missing at every level.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Level {
+    /// Pressure (hPa)
+    pub pressure: Optioned<HectoPascal>,
+    /// Temperature (C)
+    pub temperature: Optioned<Celsius>,
+    /// Wet bulb temperature (C)
+    pub wet_bulb: Optioned<Celsius>,
+    /// Dew point (C)
+    pub dew_point: Optioned<Celsius>,
+    /// Equivalent potential temperature (K)
+    pub theta_e: Optioned<Kelvin>,
+    /// Wind
+    pub wind: Optioned<WindSpdDir<Knots>>,
+    /// Pressure vertical velocity (Pa/sec)
+    pub omega: Optioned<PaPS>,
+    /// Height above MSL in meters
+    pub height: Optioned<Meters>,
+    /// Cloud fraction (0 to 1)
+    pub cloud_fraction: Optioned<f64>,
+    /// Mixing ratio (g/kg)
+    pub mixing_ratio: Optioned<f64>,
 }
 
 impl UpperAir {
     /// Given a string slice, attempt to parse it into a UpperAir.
-    pub fn parse(text: &str) -> Result<UpperAir, Box<dyn Error>> {
+    ///
+    /// The text is a single sounding from the upper air section of a bufkit file, the station
+    /// info, indexes, and profile. Errors have the line number where they happened, relative to
+    /// the start of `text`.
+    ///
+    /// ```
+    /// use sounding_bufkit::UpperAir;
+    ///
+    /// let text = "STID = KMSO STNM = 727730 TIME = 170401/0100
+    ///             SLAT = 46.87 SLON = -114.16 SELV = 1335.0
+    ///             STIM = 1
+    ///
+    ///             SHOW = 8.12 LIFT = 8.00 SWET = 39.08 KINX = 14.88
+    ///             LCLP = 780.77 PWAT = 9.28 TOTL = 39.55 CAPE = 0.00
+    ///             LCLT = 272.88 CINS = 0.00 EQLV = -9999.00 LFCT = -9999.00
+    ///             BRCH = 0.00
+    ///
+    ///             PRES TMPC TMWC DWPC THTE DRCT SKNT OMEG
+    ///             CFRL HGHT
+    ///             867.20 8.04 4.71 1.19 307.17 288.43 2.45 0.00
+    ///             0.00 1353.07
+    ///             863.50 7.64 4.42 0.99 306.96 293.63 3.40 0.00
+    ///             0.00 1388.34";
+    ///
+    /// let snd = UpperAir::parse(text).unwrap();
+    /// assert!(snd.validate().is_ok());
+    /// assert_eq!(snd.num, 727730);
+    /// assert_eq!(snd.pressure.len(), 2);
+    /// assert!(snd.eqlv.is_none());
+    /// ```
+    pub fn parse(text: &str) -> Result<UpperAir, BufkitFileError> {
+        UpperAir::parse_with(text, &mut ParseBuffer::default())
+    }
+
+    /// Same as `parse`, but reuse the scratch space and station id in `buf`.
+    pub(crate) fn parse_with(
+        text: &str,
+        buf: &mut ParseBuffer,
+    ) -> Result<UpperAir, BufkitFileError> {
         use self::indexes::Indexes;
         use self::profile::Profile;
         use self::station_info::StationInfo;
-        use crate::parse_util::find_blank_line;
+        use crate::parse_util::{find_blank_line, line_of};
 
-        let mut break_point = find_blank_line(text).ok_or_else(BufkitFileError::new)?;
+        let mut break_point = find_blank_line(text)
+            .ok_or_else(|| BufkitFileError::MissingSection("station info".to_owned()))?;
         let (station_info_section, the_rest) = text.split_at(break_point);
 
-        break_point = find_blank_line(the_rest).ok_or_else(BufkitFileError::new)?;
-        let (index_section, upper_air_section) = the_rest.split_at(break_point);
+        // Some minimal exports leave out the index block, so the profile comes right after the
+        // station info. In that case all of the indexes are missing.
+        let (index_section, upper_air_section) = if Profile::starts_with_header(the_rest) {
+            ("", the_rest)
+        } else {
+            break_point = find_blank_line(the_rest)
+                .ok_or_else(|| BufkitFileError::MissingSection("indexes".to_owned()))?;
+            the_rest.split_at(break_point)
+        };
 
-        let station_info = StationInfo::parse(station_info_section)?;
-        let indexes = Indexes::parse(index_section)?;
-        let upper_air = Profile::parse(upper_air_section)?;
+        let station_info = StationInfo::parse(station_info_section, &buf.options)
+            .map_err(|err| err.located(line_of(text, station_info_section)))?;
+        let indexes = Indexes::parse(index_section, &buf.options)
+            .map_err(|err| err.located(line_of(text, index_section)))?;
+        let upper_air = Profile::parse(upper_air_section, &mut buf.profile, &buf.options)
+            .map_err(|err| err.located(line_of(text, upper_air_section)))?;
 
         Ok(UpperAir {
             // Station info
             num: station_info.num,
             valid_time: station_info.valid_time,
             lead_time: station_info.lead_time,
-            id: station_info.id,
+            id: station_info.id.map(|id| buf.shared_station_id(id)),
             lat: station_info.lat,
             lon: station_info.lon,
             elevation: station_info.elevation,
@@ -104,22 +353,425 @@ impl UpperAir {
             omega: upper_air.omega,
             height: upper_air.height,
             cloud_fraction: upper_air.cloud_fraction,
+            mixing_ratio: upper_air.mixing_ratio,
         })
     }
 
-    /// Validate the sounding
+    /// Parse only the valid time from a string slice that could be parsed into an UpperAir.
+    ///
+    /// This is much cheaper than parsing the whole profile.
+    pub(crate) fn parse_valid_time(text: &str) -> Result<NaiveDateTime, BufkitFileError> {
+        self::station_info::StationInfo::parse_valid_time(text)
+    }
+
+    /// Parse only the station number from a string slice that could be parsed into an UpperAir.
+    pub(crate) fn parse_station_num(text: &str) -> Result<i32, BufkitFileError> {
+        self::station_info::StationInfo::parse_station_num(text)
+    }
+
+    /// Parse only the latitude, longitude, and elevation from a string slice that could be
+    /// parsed into an UpperAir.
+    pub(crate) fn parse_location(text: &str) -> Result<Location, BufkitFileError> {
+        use crate::parse_util::find_blank_line;
+
+        let station_info_section = &text[..find_blank_line(text).unwrap_or(text.len())];
+        let station_info =
+            self::station_info::StationInfo::parse(station_info_section, &ParseOptions::default())?;
+
+        Ok((station_info.lat, station_info.lon, station_info.elevation))
+    }
+
+    /// Get the initialization time of the model run that produced this sounding.
+    pub fn init_time(&self) -> NaiveDateTime {
+        self.valid_time - chrono::Duration::hours(i64::from(self.lead_time))
+    }
+
+    /// Check if the model provided convective indexes for this sounding.
+    ///
+    /// This is true when CAPE is present, even if it is zero, and at least one of the level of
+    /// free convection or the equilibrium level is present. A stable sounding usually has CAPE of
+    /// zero with both levels missing, so this is false for it.
+    pub fn has_convective_indices(&self) -> bool {
+        self.cape.is_some() && (self.lfc.is_some() || self.eqlv.is_some())
+    }
+
+    /// Get the forecast lead time as a `Duration`, or `None` if the lead time is missing.
+    pub fn lead_duration(&self) -> Option<chrono::Duration> {
+        check_missing_i32(self.lead_time).map(|hours| chrono::Duration::hours(i64::from(hours)))
+    }
+
+    /// Interpolate this sounding and `other` onto a common set of pressure levels so they can be
+    /// compared level by level.
+    ///
+    /// The common levels are the union of the pressure levels in both soundings, ordered from
+    /// highest to lowest pressure. Values are interpolated linearly in the natural log of pressure.
+    /// Where one sounding does not extend to a level in the other one, its values at that level
+    /// are missing.
+    pub fn align_to(&self, other: &UpperAir) -> (UpperAir, UpperAir) {
+        let mut levels: Vec<HectoPascal> = self
+            .pressure
+            .iter()
+            .chain(other.pressure.iter())
+            .filter_map(|p| p.into_option())
+            .collect();
+        levels.sort_by(|a, b| b.partial_cmp(a).unwrap());
+        levels.dedup();
+
+        (self.interpolate_to(&levels), other.interpolate_to(&levels))
+    }
+
+    /// Create a copy of this sounding with the profiles interpolated to the given levels.
+    fn interpolate_to(&self, levels: &[HectoPascal]) -> UpperAir {
+        use self::interpolation::{interpolate, interpolate_wind};
+        use optional::some;
+
+        let p = &self.pressure;
+
+        UpperAir {
+            // Station info
+            num: self.num,
+            valid_time: self.valid_time,
+            lead_time: self.lead_time,
+            id: self.id.clone(),
+            lat: self.lat,
+            lon: self.lon,
+            elevation: self.elevation,
+
+            // Indexes
+            show: self.show,
+            li: self.li,
+            swet: self.swet,
+            kinx: self.kinx,
+            lclp: self.lclp,
+            pwat: self.pwat,
+            totl: self.totl,
+            cape: self.cape,
+            lclt: self.lclt,
+            cins: self.cins,
+            eqlv: self.eqlv,
+            lfc: self.lfc,
+            brch: self.brch,
+
+            // Upper air
+            pressure: levels.iter().map(|&lvl| some(lvl)).collect(),
+            temperature: levels
+                .iter()
+                .map(|&lvl| interpolate(p, &self.temperature, lvl))
+                .collect(),
+            wet_bulb: levels
+                .iter()
+                .map(|&lvl| interpolate(p, &self.wet_bulb, lvl))
+                .collect(),
+            dew_point: levels
+                .iter()
+                .map(|&lvl| interpolate(p, &self.dew_point, lvl))
+                .collect(),
+            theta_e: levels
+                .iter()
+                .map(|&lvl| interpolate(p, &self.theta_e, lvl))
+                .collect(),
+            wind: levels
+                .iter()
+                .map(|&lvl| interpolate_wind(p, &self.wind, lvl))
+                .collect(),
+            omega: levels
+                .iter()
+                .map(|&lvl| interpolate(p, &self.omega, lvl))
+                .collect(),
+            height: levels
+                .iter()
+                .map(|&lvl| interpolate(p, &self.height, lvl))
+                .collect(),
+            cloud_fraction: levels
+                .iter()
+                .map(|&lvl| interpolate(p, &self.cloud_fraction, lvl))
+                .collect(),
+            mixing_ratio: levels
+                .iter()
+                .map(|&lvl| interpolate(p, &self.mixing_ratio, lvl))
+                .collect(),
+        }
+    }
+
+    /// Get the highest and lowest pressures in the profile, the bottom and top of the sounding.
+    ///
+    /// Missing pressures are skipped. Returns `None` if every pressure is missing.
+    pub fn pressure_range(&self) -> Option<(HectoPascal, HectoPascal)> {
+        self.pressure
+            .iter()
+            .filter_map(|p| p.into_option())
+            .fold(None, |range, p| match range {
+                None => Some((p, p)),
+                Some((bottom, top)) => Some((
+                    if p > bottom { p } else { bottom },
+                    if p < top { p } else { top },
+                )),
+            })
+    }
+
+    /// Get the value of a profile at pressure `p`, interpolated linearly in the natural log of
+    /// pressure.
+    ///
+    /// The value is in the same units as the profile, and `ProfileField::Wind` gives the wind
+    /// speed in knots. Returns none if `p` is outside the profile, or if either of the levels
+    /// bracketing it is missing the value.
+    pub fn value_at_pressure(&self, p: HectoPascal, field: ProfileField) -> Optioned<f64> {
+        use self::interpolation::{interpolate, interpolate_wind};
+        use metfor::Quantity;
+
+        fn unpacked<T: Quantity + optional::Noned>(val: Optioned<T>) -> Optioned<f64> {
+            val.map_t(|val| val.unpack())
+        }
+
+        let pres = &self.pressure;
+        match field {
+            ProfileField::Pressure => unpacked(interpolate(pres, pres, p)),
+            ProfileField::Temperature => unpacked(interpolate(pres, &self.temperature, p)),
+            ProfileField::WetBulb => unpacked(interpolate(pres, &self.wet_bulb, p)),
+            ProfileField::DewPoint => unpacked(interpolate(pres, &self.dew_point, p)),
+            ProfileField::ThetaE => unpacked(interpolate(pres, &self.theta_e, p)),
+            ProfileField::Wind => interpolate_wind(pres, &self.wind, p).map_t(|w| w.speed.unpack()),
+            ProfileField::Omega => unpacked(interpolate(pres, &self.omega, p)),
+            ProfileField::Height => unpacked(interpolate(pres, &self.height, p)),
+            ProfileField::CloudFraction => interpolate(pres, &self.cloud_fraction, p),
+            ProfileField::MixingRatio => interpolate(pres, &self.mixing_ratio, p),
+        }
+    }
+
+    /// Get a profile as plain `f64` values, with `f64::NAN` in place of missing values.
+    ///
+    /// The values are in the same units as the profile, and `ProfileField::Wind` gives the wind
+    /// speed in knots. This doesn't change the sounding, it copies the values into a new `Vec`.
+    pub fn profile_as_f64_nan(&self, field: ProfileField) -> Vec<f64> {
+        use metfor::Quantity;
+
+        fn nan_filled<T: Quantity + optional::Noned>(vals: &[Optioned<T>]) -> Vec<f64> {
+            vals.iter()
+                .map(|val| val.map_or(f64::NAN, |val| val.unpack()))
+                .collect()
+        }
+
+        match field {
+            ProfileField::Pressure => nan_filled(&self.pressure),
+            ProfileField::Temperature => nan_filled(&self.temperature),
+            ProfileField::WetBulb => nan_filled(&self.wet_bulb),
+            ProfileField::DewPoint => nan_filled(&self.dew_point),
+            ProfileField::ThetaE => nan_filled(&self.theta_e),
+            ProfileField::Wind => self
+                .wind
+                .iter()
+                .map(|wind| wind.map_or(f64::NAN, |wind| wind.speed.unpack()))
+                .collect(),
+            ProfileField::Omega => nan_filled(&self.omega),
+            ProfileField::Height => nan_filled(&self.height),
+            ProfileField::CloudFraction => nan_filled(&self.cloud_fraction),
+            ProfileField::MixingRatio => nan_filled(&self.mixing_ratio),
+        }
+    }
+
+    /// Count the missing values in each profile.
+    ///
+    /// Every field has an entry. A profile that isn't in the sounding at all is empty, so it has
+    /// no missing values.
+    pub fn missing_counts(&self) -> HashMap<ProfileField, usize> {
+        fn count<T: optional::Noned + Copy>(vals: &[Optioned<T>]) -> usize {
+            vals.iter().filter(|val| val.is_none()).count()
+        }
+
+        let mut counts = HashMap::new();
+        counts.insert(ProfileField::Pressure, count(&self.pressure));
+        counts.insert(ProfileField::Temperature, count(&self.temperature));
+        counts.insert(ProfileField::WetBulb, count(&self.wet_bulb));
+        counts.insert(ProfileField::DewPoint, count(&self.dew_point));
+        counts.insert(ProfileField::ThetaE, count(&self.theta_e));
+        counts.insert(ProfileField::Wind, count(&self.wind));
+        counts.insert(ProfileField::Omega, count(&self.omega));
+        counts.insert(ProfileField::Height, count(&self.height));
+        counts.insert(ProfileField::CloudFraction, count(&self.cloud_fraction));
+        counts.insert(ProfileField::MixingRatio, count(&self.mixing_ratio));
+        counts
+    }
+
+    /// Get the temperature lapse rate between two pressure levels in C/km.
+    ///
+    /// The temperature and height at each level are interpolated like `value_at_pressure`, and a
+    /// positive lapse rate means the temperature decreases with height. Returns none if either
+    /// level is outside the profile, or the temperature or height is missing there.
+    pub fn lapse_rate(&self, p_bottom: HectoPascal, p_top: HectoPascal) -> Optioned<f64> {
+        let value = |p, field| self.value_at_pressure(p, field).into_option();
+
+        let lapse_rate = || {
+            let t_bottom = value(p_bottom, ProfileField::Temperature)?;
+            let t_top = value(p_top, ProfileField::Temperature)?;
+            let h_bottom = value(p_bottom, ProfileField::Height)?;
+            let h_top = value(p_top, ProfileField::Height)?;
+
+            if h_top == h_bottom {
+                return None;
+            }
+            Some((t_bottom - t_top) / (h_top - h_bottom) * 1000.0)
+        };
+
+        Optioned::from(lapse_rate())
+    }
+
+    /// Interpolate the temperature, dew point, and wind to the mandatory pressure levels.
+    ///
+    /// The mandatory levels are 1000, 925, 850, 700, 500, 300, 250, and 200 hPa. Levels outside
+    /// the pressure range of the profile, e.g. 1000 hPa at a high elevation station, are left out.
+    pub fn at_mandatory_levels(&self) -> Vec<MandatoryLevel> {
+        use self::interpolation::{interpolate, interpolate_wind};
+
+        const MANDATORY_LEVELS: [f64; 8] =
+            [1000.0, 925.0, 850.0, 700.0, 500.0, 300.0, 250.0, 200.0];
+
+        let (bottom, top) = self
+            .pressure
+            .iter()
+            .filter_map(|p| p.into_option())
+            .fold((f64::MIN, f64::MAX), |(bottom, top), HectoPascal(p)| {
+                (bottom.max(p), top.min(p))
+            });
+
+        let p = &self.pressure;
+        MANDATORY_LEVELS
+            .iter()
+            .filter(|&&lvl| lvl <= bottom && lvl >= top)
+            .map(|&lvl| HectoPascal(lvl))
+            .map(|lvl| {
+                (
+                    lvl,
+                    interpolate(p, &self.temperature, lvl),
+                    interpolate(p, &self.dew_point, lvl),
+                    interpolate_wind(p, &self.wind, lvl),
+                )
+            })
+            .collect()
+    }
+
+    /// Find the layers that are above freezing (melting) and below freezing (refreezing), from
+    /// the lowest level up.
+    ///
+    /// Each layer is given as the height (MSL) of its bottom and top, and `true` if it is a warm
+    /// (above freezing) layer. The boundaries between layers are linearly interpolated in height to
+    /// where the temperature crosses 0C. Levels missing either the temperature or height are
+    /// skipped.
+    pub fn melting_refreezing_layers(&self) -> Vec<(Meters, Meters, bool)> {
+        use metfor::Quantity;
+
+        let mut levels = self
+            .temperature
+            .iter()
+            .zip(self.height.iter())
+            .filter_map(|(t, h)| {
+                t.into_option()
+                    .and_then(|t| h.into_option().map(|h| (t.unpack(), h.unpack())))
+            });
+
+        let mut layers = vec![];
+
+        let (mut prev_t, mut prev_h) = match levels.next() {
+            Some(level) => level,
+            None => return layers,
+        };
+        let mut bottom = prev_h;
+        let mut warm = prev_t > 0.0;
+
+        for (t, h) in levels {
+            if (t > 0.0) != warm {
+                let crossing = prev_h + (0.0 - prev_t) * (h - prev_h) / (t - prev_t);
+                layers.push((Meters(bottom), Meters(crossing), warm));
+                bottom = crossing;
+                warm = !warm;
+            }
+            prev_t = t;
+            prev_h = h;
+        }
+
+        if prev_h > bottom {
+            layers.push((Meters(bottom), Meters(prev_h), warm));
+        }
+
+        layers
+    }
+
+    /// Find every level where the temperature crosses 0C, from the bottom up.
+    ///
+    /// The pressure and height at each crossing are interpolated linearly between the levels on
+    /// either side. Levels missing the temperature, pressure, or height are skipped. The vector is
+    /// empty if the whole column is above or below freezing.
+    pub fn freezing_levels(&self) -> Vec<(HectoPascal, Meters)> {
+        self.zero_crossings(&self.temperature)
+    }
+
+    /// Get the highest height where the wet bulb temperature crosses 0C, the wet bulb zero.
+    ///
+    /// The height is interpolated linearly between the levels on either side of the crossing.
+    /// Levels with a missing wet bulb, like the upper levels in many files, are skipped. Returns
+    /// none if the wet bulb never crosses 0C.
+    pub fn wet_bulb_zero(&self) -> Optioned<Meters> {
+        Optioned::from(
+            self.zero_crossings(&self.wet_bulb)
+                .last()
+                .map(|&(_, height)| height),
+        )
+    }
+
+    /// Find where a temperature profile crosses 0C, see `freezing_levels`.
+    fn zero_crossings(&self, temperature: &[Optioned<Celsius>]) -> Vec<(HectoPascal, Meters)> {
+        use metfor::Quantity;
+
+        let mut levels = temperature
+            .iter()
+            .zip(&self.pressure)
+            .zip(&self.height)
+            .filter_map(|((t, p), h)| {
+                let (t, p, h) = (t.into_option()?, p.into_option()?, h.into_option()?);
+                Some((t.unpack(), p.unpack(), h.unpack()))
+            });
+
+        let mut crossings = vec![];
+
+        let (mut prev_t, mut prev_p, mut prev_h) = match levels.next() {
+            Some(level) => level,
+            None => return crossings,
+        };
+
+        for (t, p, h) in levels {
+            if (t > 0.0) != (prev_t > 0.0) {
+                let frac = (0.0 - prev_t) / (t - prev_t);
+                crossings.push((
+                    HectoPascal(prev_p + frac * (p - prev_p)),
+                    Meters(prev_h + frac * (h - prev_h)),
+                ));
+            }
+            prev_t = t;
+            prev_p = p;
+            prev_h = h;
+        }
+
+        crossings
+    }
+
+    /// Validate the sounding.
+    ///
+    /// There must be a pressure profile, and every other profile must be empty or the same length
+    /// as the pressure profile.
     pub fn validate(&self) -> Result<(), BufkitFileError> {
         // Pressure is mandatory
         let len = self.pressure.len();
         if len == 0 {
-            return Err(BufkitFileError::new());
+            return Err(BufkitFileError::EmptyProfile);
         }
 
         let is_valid_length = |l| {
             if l == 0 || l == len {
                 Ok(())
             } else {
-                Err(BufkitFileError::new())
+                Err(BufkitFileError::ProfileLengthMismatch {
+                    expected: len,
+                    found: l,
+                })
             }
         };
 
@@ -131,9 +783,407 @@ impl UpperAir {
         is_valid_length(self.omega.len())?;
         is_valid_length(self.height.len())?;
         is_valid_length(self.cloud_fraction.len())?;
+        is_valid_length(self.mixing_ratio.len())?;
 
         Ok(())
     }
+
+    /// Validate the sounding like `validate`, and also check that every level has a value for
+    /// each of the `required` profiles.
+    pub fn validate_complete(&self, required: &[ProfileField]) -> Result<(), BufkitFileError> {
+        use optional::Noned;
+
+        fn first_missing<T: Noned + Copy>(vals: &[Optioned<T>]) -> Option<usize> {
+            vals.iter().position(|val| val.is_none())
+        }
+
+        self.validate()?;
+
+        for &field in required {
+            let (len, missing_level) = match field {
+                ProfileField::Pressure => (self.pressure.len(), first_missing(&self.pressure)),
+                ProfileField::Temperature => {
+                    (self.temperature.len(), first_missing(&self.temperature))
+                }
+                ProfileField::WetBulb => (self.wet_bulb.len(), first_missing(&self.wet_bulb)),
+                ProfileField::DewPoint => (self.dew_point.len(), first_missing(&self.dew_point)),
+                ProfileField::ThetaE => (self.theta_e.len(), first_missing(&self.theta_e)),
+                ProfileField::Wind => (self.wind.len(), first_missing(&self.wind)),
+                ProfileField::Omega => (self.omega.len(), first_missing(&self.omega)),
+                ProfileField::Height => (self.height.len(), first_missing(&self.height)),
+                ProfileField::CloudFraction => (
+                    self.cloud_fraction.len(),
+                    first_missing(&self.cloud_fraction),
+                ),
+                ProfileField::MixingRatio => {
+                    (self.mixing_ratio.len(), first_missing(&self.mixing_ratio))
+                }
+            };
+
+            if len == 0 {
+                return Err(BufkitFileError::MissingProfile(field));
+            }
+            if let Some(level) = missing_level {
+                return Err(BufkitFileError::ProfileGap { field, level });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate the sounding like `validate`, and also check that the pressure decreases from
+    /// the first level to the last.
+    ///
+    /// Missing pressure values are skipped.
+    pub fn validate_strict(&self) -> Result<(), BufkitFileError> {
+        self.validate()?;
+
+        let mut last_pressure: Option<HectoPascal> = None;
+        for (level, p) in self.pressure.iter().enumerate() {
+            if let Some(p) = p.into_option() {
+                if last_pressure.is_some_and(|last| p >= last) {
+                    return Err(BufkitFileError::PressureNotDecreasing { level });
+                }
+                last_pressure = Some(p);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find the levels with the same pressure as the level before them, which breaks
+    /// interpolation.
+    ///
+    /// Missing pressure values are skipped, so a level is compared to the closest level below it
+    /// with a pressure.
+    pub fn duplicate_levels(&self) -> Vec<usize> {
+        let mut last_pressure: Option<HectoPascal> = None;
+        let mut duplicates = vec![];
+
+        for (level, p) in self.pressure.iter().enumerate() {
+            if let Some(p) = p.into_option() {
+                if last_pressure == Some(p) {
+                    duplicates.push(level);
+                }
+                last_pressure = Some(p);
+            }
+        }
+
+        duplicates
+    }
+
+    /// Remove the levels found by `duplicate_levels` from every profile, so the lengths stay
+    /// consistent.
+    ///
+    /// The first of the repeated levels is kept.
+    pub fn dedup_levels(&mut self) {
+        let levels = self.duplicate_levels();
+        self.remove_levels(&levels);
+    }
+
+    /// Remove every level with a pressure lower than `p`, so only the levels at or below `p` are
+    /// left.
+    ///
+    /// A level exactly at `p` is kept, and so are levels with a missing pressure.
+    pub fn truncate_above(&mut self, p: HectoPascal) {
+        let levels: Vec<usize> = self
+            .pressure
+            .iter()
+            .enumerate()
+            .filter(|(_, pres)| pres.into_option().is_some_and(|pres| pres < p))
+            .map(|(i, _)| i)
+            .collect();
+        self.remove_levels(&levels);
+    }
+
+    /// Remove the levels with the indexes in `levels`, which must be sorted, from every profile.
+    fn remove_levels(&mut self, levels: &[usize]) {
+        fn remove<T>(vals: &mut Vec<T>, levels: &[usize]) {
+            let mut level = 0;
+            vals.retain(|_| {
+                let keep = levels.binary_search(&level).is_err();
+                level += 1;
+                keep
+            });
+        }
+
+        if levels.is_empty() {
+            return;
+        }
+
+        remove(&mut self.pressure, levels);
+        remove(&mut self.temperature, levels);
+        remove(&mut self.wet_bulb, levels);
+        remove(&mut self.dew_point, levels);
+        remove(&mut self.theta_e, levels);
+        remove(&mut self.wind, levels);
+        remove(&mut self.omega, levels);
+        remove(&mut self.height, levels);
+        remove(&mut self.cloud_fraction, levels);
+        remove(&mut self.mixing_ratio, levels);
+    }
+
+    /// Iterate over the levels of the sounding, with the values of every profile for each one.
+    ///
+    /// There is one `Level` for each pressure level, in the same order as the profiles.
+    pub fn levels(&self) -> impl Iterator<Item = Level> + '_ {
+        fn at<T: optional::Noned + Copy>(vals: &[Optioned<T>], i: usize) -> Optioned<T> {
+            vals.get(i).copied().unwrap_or_default()
+        }
+
+        (0..self.pressure.len()).map(move |i| Level {
+            pressure: at(&self.pressure, i),
+            temperature: at(&self.temperature, i),
+            wet_bulb: at(&self.wet_bulb, i),
+            dew_point: at(&self.dew_point, i),
+            theta_e: at(&self.theta_e, i),
+            wind: at(&self.wind, i),
+            omega: at(&self.omega, i),
+            height: at(&self.height, i),
+            cloud_fraction: at(&self.cloud_fraction, i),
+            mixing_ratio: at(&self.mixing_ratio, i),
+        })
+    }
+
+    /// Get the cloud fraction profile as fractions from 0 to 1, the way it is stored.
+    pub fn cloud_fraction_ratio(&self) -> &[Optioned<f64>] {
+        &self.cloud_fraction
+    }
+
+    /// Get the cloud fraction profile as percentages from 0 to 100, the way it is in the file.
+    pub fn cloud_fraction_percent(&self) -> Vec<Optioned<f64>> {
+        self.cloud_fraction
+            .iter()
+            .map(|cf| cf.map_t(|cf| cf * 100.0))
+            .collect()
+    }
+
+    /// Calculate the potential temperature at each level from the pressure and temperature.
+    ///
+    /// This isn't stored in the sounding, so it is calculated each time. Levels where the
+    /// pressure or temperature is missing are missing.
+    pub fn theta_profile(&self) -> Vec<Optioned<Kelvin>> {
+        self.levels()
+            .map(|level| {
+                level.pressure.and_then(|p| {
+                    level
+                        .temperature
+                        .map_t(|t| metfor::potential_temperature(p, t))
+                })
+            })
+            .collect()
+    }
+
+    /// Calculate the mixing ratio (g/kg) at each level from the pressure and dew point.
+    ///
+    /// This is calculated even if the sounding has a mixing ratio profile. Levels where the
+    /// pressure or dew point is missing, or out of range for metfor, are missing.
+    pub fn mixing_ratio_profile(&self) -> Vec<Optioned<f64>> {
+        self.levels()
+            .map(|level| {
+                level.pressure.and_then(|p| {
+                    level.dew_point.and_then(|dp| {
+                        Optioned::from(metfor::mixing_ratio(dp, p)).map_t(|mw| mw * 1000.0)
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Calculate the relative humidity with respect to liquid water at each level from the
+    /// temperature and dew point.
+    ///
+    /// The values are decimals, i.e. 0.95 instead of 95%. Levels where the temperature or dew
+    /// point is missing, or out of range for metfor, are missing.
+    pub fn relative_humidity_profile(&self) -> Vec<Optioned<f64>> {
+        self.levels()
+            .map(|level| {
+                level.temperature.and_then(|t| {
+                    level
+                        .dew_point
+                        .and_then(|dp| Optioned::from(metfor::rh(t, dp)))
+                })
+            })
+            .collect()
+    }
+
+    /// Calculate the precipitable water by integrating the mixing ratio from
+    /// `mixing_ratio_profile` over pressure.
+    ///
+    /// Levels where the pressure or mixing ratio is missing are skipped, and the layers between
+    /// the remaining levels are integrated with the trapezoid rule. This is useful when `pwat` is
+    /// missing, or to check it. Returns `none()` if fewer than two levels have moisture data.
+    pub fn compute_precipitable_water(&self) -> Optioned<Mm> {
+        let levels: Vec<(f64, f64)> = self
+            .pressure
+            .iter()
+            .zip(self.mixing_ratio_profile())
+            .filter_map(|(p, mw)| Some((p.into_option()?.0, mw.into_option()?)))
+            .collect();
+
+        if levels.len() < 2 {
+            return optional::none();
+        }
+
+        // Mixing ratio (kg/kg) times pressure (Pa) divided by gravity is kg/m**2, or mm of water.
+        let total: f64 = levels
+            .windows(2)
+            .map(|pair| {
+                let ((p0, mw0), (p1, mw1)) = (pair[0], pair[1]);
+                (mw0 + mw1) / 2.0 / 1000.0 * (p0 - p1).abs() * 100.0
+            })
+            .sum();
+
+        optional::some(Mm(total / -metfor::g))
+    }
+
+    /// Make sure the levels are ordered from the surface up, so pressure decreases with index.
+    ///
+    /// If the first pressure is lower than the last one, ignoring missing values, every profile
+    /// is reversed together. Otherwise nothing changes.
+    pub fn order_surface_to_top(&mut self) {
+        let mut pressures = self.pressure.iter().filter_map(|p| p.into_option());
+        let bottom = pressures.next();
+        let top = pressures.next_back();
+
+        if let (Some(bottom), Some(top)) = (bottom, top) {
+            if bottom < top {
+                self.pressure.reverse();
+                self.temperature.reverse();
+                self.wet_bulb.reverse();
+                self.dew_point.reverse();
+                self.theta_e.reverse();
+                self.wind.reverse();
+                self.omega.reverse();
+                self.height.reverse();
+                self.cloud_fraction.reverse();
+                self.mixing_ratio.reverse();
+            }
+        }
+    }
+
+    /// Get a hash of the values in the sounding, for caching or detecting changes.
+    ///
+    /// The station info, valid time, indexes, and every profile are hashed in a fixed order, so
+    /// the same data formatted differently in two files has the same hash. The hash is 64 bit
+    /// FNV-1a, which doesn't depend on the platform or version of Rust, so it is safe to store.
+    /// It is not a cryptographic hash.
+    pub fn content_hash(&self) -> u64 {
+        let mut hash = ContentHash::new();
+
+        // Station info
+        hash.write(&self.num.to_le_bytes());
+        hash.write(&self.valid_time.and_utc().timestamp().to_le_bytes());
+        hash.write(&self.lead_time.to_le_bytes());
+        match &self.id {
+            Some(id) => {
+                hash.write(&[1]);
+                hash.write(&(id.len() as u64).to_le_bytes());
+                hash.write(id.as_bytes());
+            }
+            None => hash.write(&[0]),
+        }
+        hash.value(self.lat);
+        hash.value(self.lon);
+        hash.value(self.elevation);
+
+        // Indexes
+        hash.value(self.show);
+        hash.value(self.li);
+        hash.value(self.swet);
+        hash.value(self.kinx);
+        hash.value(self.lclp);
+        hash.value(self.pwat);
+        hash.value(self.totl);
+        hash.value(self.cape);
+        hash.value(self.lclt);
+        hash.value(self.cins);
+        hash.value(self.eqlv);
+        hash.value(self.lfc);
+        hash.value(self.brch);
+
+        // Profiles
+        hash.profile(&self.pressure);
+        hash.profile(&self.temperature);
+        hash.profile(&self.wet_bulb);
+        hash.profile(&self.dew_point);
+        hash.profile(&self.theta_e);
+        hash.write(&(self.wind.len() as u64).to_le_bytes());
+        for wind in &self.wind {
+            hash.value(wind.map_t(|wind| wind.direction));
+            hash.value(wind.map_t(|wind| wind.speed));
+        }
+        hash.profile(&self.omega);
+        hash.profile(&self.height);
+        hash.profile(&self.cloud_fraction);
+        hash.profile(&self.mixing_ratio);
+
+        hash.0
+    }
+}
+
+/// A 64 bit FNV-1a hash, used by `UpperAir::content_hash`.
+struct ContentHash(u64);
+
+impl ContentHash {
+    fn new() -> Self {
+        ContentHash(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    /// Hash a value, with a marker so missing values are different from any other value.
+    fn value<T: metfor::Quantity + optional::Noned>(&mut self, val: Optioned<T>) {
+        match val.into_option() {
+            Some(val) => {
+                self.write(&[1]);
+                self.write(&val.unpack().to_bits().to_le_bytes());
+            }
+            None => self.write(&[0]),
+        }
+    }
+
+    /// Hash the number of levels and then every value in a profile.
+    fn profile<T: metfor::Quantity + optional::Noned>(&mut self, vals: &[Optioned<T>]) {
+        self.write(&(vals.len() as u64).to_le_bytes());
+        for &val in vals {
+            self.value(val);
+        }
+    }
+}
+
+impl std::fmt::Display for UpperAir {
+    /// A one line summary with the station, valid time, pressure levels, and whether CAPE and CIN
+    /// are available.
+    ///
+    /// Missing values are shown as `--`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use metfor::Quantity;
+
+        let pressure = |p: Option<&Optioned<HectoPascal>>| match p.and_then(|p| p.into_option()) {
+            Some(p) => format!("{:.1} hPa", p.unpack()),
+            None => "--".to_owned(),
+        };
+        let present = |present: bool| if present { "yes" } else { "no" };
+
+        write!(
+            f,
+            "{} {} {}: {} levels from {} to {}, CAPE: {}, CIN: {}",
+            self.num,
+            self.id.as_deref().unwrap_or("--"),
+            self.valid_time.format("%Y-%m-%d %H:%MZ"),
+            self.pressure.len(),
+            pressure(self.pressure.first()),
+            pressure(self.pressure.last()),
+            present(self.cape.is_some()),
+            present(self.cins.is_some()),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -274,6 +1324,397 @@ mod test {
          0.00 32545.28"
     }
 
+    #[test]
+    fn test_validate_complete() {
+        use optional::none;
+
+        let required = [
+            ProfileField::Pressure,
+            ProfileField::Temperature,
+            ProfileField::Height,
+        ];
+
+        let mut snd = UpperAir::parse(get_test_data()).unwrap();
+        assert!(snd.validate_complete(&required).is_ok());
+        assert_eq!(
+            snd.validate_complete(&[ProfileField::MixingRatio]),
+            Err(BufkitFileError::MissingProfile(ProfileField::MixingRatio))
+        );
+
+        // The dew point is missing near the top of the sounding.
+        let first_missing = snd.dew_point.iter().position(|dp| dp.is_none()).unwrap();
+        assert_eq!(
+            snd.validate_complete(&[ProfileField::DewPoint]),
+            Err(BufkitFileError::ProfileGap {
+                field: ProfileField::DewPoint,
+                level: first_missing,
+            })
+        );
+
+        snd.height[5] = none();
+        assert_eq!(
+            snd.validate_complete(&required),
+            Err(BufkitFileError::ProfileGap {
+                field: ProfileField::Height,
+                level: 5
+            })
+        );
+
+        snd.temperature.clear();
+        assert!(snd.validate().is_ok());
+        assert_eq!(
+            snd.validate_complete(&required),
+            Err(BufkitFileError::MissingProfile(ProfileField::Temperature))
+        );
+    }
+
+    #[test]
+    fn test_validate_strict() {
+        use optional::{none, some};
+
+        let mut snd = UpperAir::parse(get_test_data()).unwrap();
+        assert!(snd.validate_strict().is_ok());
+
+        // Missing values are skipped.
+        snd.pressure[1] = none();
+        assert!(snd.validate_strict().is_ok());
+
+        snd.pressure.swap(3, 4);
+        assert!(snd.validate().is_ok());
+        assert_eq!(
+            snd.validate_strict(),
+            Err(BufkitFileError::PressureNotDecreasing { level: 4 })
+        );
+
+        snd.pressure.swap(3, 4);
+        snd.pressure[2] = some(HectoPascal(900.0));
+        assert_eq!(
+            snd.validate_strict(),
+            Err(BufkitFileError::PressureNotDecreasing { level: 2 })
+        );
+    }
+
+    #[test]
+    fn test_duplicate_levels() {
+        use optional::none;
+
+        let mut snd = UpperAir::parse(get_test_data()).unwrap();
+        assert!(snd.duplicate_levels().is_empty());
+
+        snd.pressure[4] = snd.pressure[3];
+        snd.pressure[9] = snd.pressure[8];
+        snd.pressure[10] = snd.pressure[8];
+        assert_eq!(snd.duplicate_levels(), vec![4, 9, 10]);
+
+        // A missing level in between doesn't hide a duplicate.
+        snd.pressure[9] = none();
+        assert_eq!(snd.duplicate_levels(), vec![4, 10]);
+    }
+
+    #[test]
+    fn test_dedup_levels() {
+        let original = UpperAir::parse(get_test_data()).unwrap();
+        let mut snd = original.clone();
+        snd.dedup_levels();
+        assert_eq!(snd, original);
+
+        snd.pressure[4] = snd.pressure[3];
+        snd.pressure[9] = snd.pressure[8];
+        snd.pressure[10] = snd.pressure[8];
+        snd.dedup_levels();
+
+        let len = original.pressure.len() - 3;
+        assert_eq!(snd.pressure.len(), len);
+        assert_eq!(snd.temperature.len(), len);
+        assert_eq!(snd.wet_bulb.len(), len);
+        assert_eq!(snd.dew_point.len(), len);
+        assert_eq!(snd.theta_e.len(), len);
+        assert_eq!(snd.wind.len(), len);
+        assert_eq!(snd.omega.len(), len);
+        assert_eq!(snd.height.len(), len);
+        assert_eq!(snd.cloud_fraction.len(), len);
+        // Empty profiles stay empty.
+        assert!(snd.mixing_ratio.is_empty());
+
+        assert!(snd.validate_strict().is_ok());
+        assert!(snd.duplicate_levels().is_empty());
+        assert_eq!(snd.temperature[3], original.temperature[3]);
+        assert_eq!(snd.temperature[4], original.temperature[5]);
+        assert_eq!(snd.temperature[7], original.temperature[8]);
+        assert_eq!(snd.temperature[8], original.temperature[11]);
+    }
+
+    #[test]
+    fn test_levels() {
+        use optional::some;
+
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+        assert_eq!(snd.levels().count(), 60);
+
+        let level = snd.levels().nth(2).unwrap();
+        assert_eq!(level.pressure, some(HectoPascal(859.8)));
+        assert_eq!(level.temperature, some(Celsius(7.24)));
+        assert_eq!(level.wet_bulb, some(Celsius(4.18)));
+        assert_eq!(level.dew_point, some(Celsius(0.90)));
+        assert_eq!(level.theta_e, some(Kelvin(306.87)));
+        assert_eq!(
+            level.wind,
+            some(WindSpdDir {
+                direction: 292.38,
+                speed: Knots(3.57)
+            })
+        );
+        assert_eq!(level.omega, some(PaPS(0.00)));
+        assert_eq!(level.height, some(Meters(1423.71)));
+        assert_eq!(level.cloud_fraction, some(0.0));
+        // There is no mixing ratio column in the sample.
+        assert!(level.mixing_ratio.is_none());
+    }
+
+    #[test]
+    fn test_missing_counts() {
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+        let counts = snd.missing_counts();
+
+        assert_eq!(counts.len(), 10);
+        assert_eq!(counts[&ProfileField::WetBulb], 8);
+        assert_eq!(counts[&ProfileField::DewPoint], 8);
+        assert_eq!(counts[&ProfileField::ThetaE], 8);
+        assert_eq!(counts[&ProfileField::Pressure], 0);
+        assert_eq!(counts[&ProfileField::Temperature], 0);
+        // There is no mixing ratio column, so nothing is missing from the empty profile.
+        assert_eq!(counts[&ProfileField::MixingRatio], 0);
+    }
+
+    #[test]
+    fn test_truncate_above() {
+        use optional::some;
+
+        let mut snd = UpperAir::parse(get_test_data()).unwrap();
+        snd.truncate_above(HectoPascal(100.0));
+
+        assert_eq!(snd.pressure.len(), 51);
+        assert!(snd.validate().is_ok());
+        assert!(snd
+            .pressure
+            .iter()
+            .all(|p| p.into_option().is_some_and(|p| p >= HectoPascal(100.0))));
+
+        // The level at the boundary is kept.
+        let top = snd.pressure[snd.pressure.len() - 1];
+        snd.truncate_above(top.unwrap());
+        assert_eq!(snd.pressure[snd.pressure.len() - 1], top);
+        assert_eq!(snd.pressure[0], some(HectoPascal(867.2)));
+    }
+
+    #[test]
+    fn test_cloud_fraction() {
+        use optional::some;
+
+        let text = get_test_data().replacen("0.00 1353.07", "45.00 1353.07", 1);
+        let snd = UpperAir::parse(&text).unwrap();
+
+        // The file has percent, but it is stored as a fraction like the surface cloud cover.
+        assert_eq!(snd.cloud_fraction[0], some(0.45));
+        assert_eq!(snd.cloud_fraction_ratio()[0], some(0.45));
+        assert_eq!(snd.cloud_fraction_percent()[0], some(45.0));
+        assert_eq!(snd.cloud_fraction_percent()[2], some(0.0));
+        assert_eq!(snd.cloud_fraction_percent().len(), snd.cloud_fraction.len());
+
+        // It is written back out as percent.
+        assert!(snd.to_bufkit_string().contains("45.00 1353.07"));
+    }
+
+    #[test]
+    fn test_theta_profile() {
+        let mut snd = UpperAir::parse(get_test_data()).unwrap();
+        let theta = snd.theta_profile();
+        assert_eq!(theta.len(), snd.pressure.len());
+
+        // 7.24 C at 859.8 hPa.
+        let expected = (7.24 + 273.15) * (1000.0f64 / 859.8).powf(287.04 / 1005.7);
+        assert!((theta[2].unwrap().0 - expected).abs() < 0.01);
+
+        snd.temperature[2] = optional::none();
+        assert!(snd.theta_profile()[2].is_none());
+    }
+
+    #[test]
+    fn test_mixing_ratio_profile() {
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+        let mw = snd.mixing_ratio_profile();
+        assert_eq!(mw.len(), snd.pressure.len());
+
+        // Dew point 1.19 C at 867.2 hPa, and 0.90 C at 859.8 hPa.
+        assert!((mw[0].unwrap() - 4.81).abs() < 0.01);
+        assert!((mw[2].unwrap() - 4.75).abs() < 0.01);
+
+        // The dew point is missing near the top of the sounding.
+        assert!(snd.dew_point[50].is_none());
+        assert!(mw[50].is_none());
+    }
+
+    #[test]
+    fn test_relative_humidity_profile() {
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+        let rh = snd.relative_humidity_profile();
+        assert_eq!(rh.len(), snd.pressure.len());
+
+        // 8.04 C with a dew point of 1.19 C, and 7.24 C with a dew point of 0.90 C.
+        assert!((rh[0].unwrap() - 0.620).abs() < 0.001);
+        assert!((rh[2].unwrap() - 0.641).abs() < 0.001);
+
+        assert!(rh[50].is_none());
+    }
+
+    #[test]
+    fn test_compute_precipitable_water() {
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+
+        // The model says 9.28 mm.
+        let provided = snd.pwat.unwrap().0;
+        let computed = snd.compute_precipitable_water().unwrap().0;
+        assert!((computed - provided).abs() / provided < 0.1);
+
+        let snd = UpperAir {
+            dew_point: vec![],
+            ..snd
+        };
+        assert!(snd.compute_precipitable_water().is_none());
+    }
+
+    #[test]
+    fn test_order_surface_to_top() {
+        let expected = UpperAir::parse(get_test_data()).unwrap();
+
+        // Already in order, so nothing changes.
+        let mut snd = expected.clone();
+        snd.order_surface_to_top();
+        assert_eq!(snd, expected);
+
+        let mut snd = expected.clone();
+        snd.pressure.reverse();
+        snd.temperature.reverse();
+        snd.wet_bulb.reverse();
+        snd.dew_point.reverse();
+        snd.theta_e.reverse();
+        snd.wind.reverse();
+        snd.omega.reverse();
+        snd.height.reverse();
+        snd.cloud_fraction.reverse();
+        snd.mixing_ratio.reverse();
+        assert_ne!(snd, expected);
+
+        snd.order_surface_to_top();
+        assert_eq!(snd, expected);
+        assert!(snd.pressure[0] > snd.pressure[1]);
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+        let hash = snd.content_hash();
+
+        // Formatting doesn't matter.
+        let reformatted = get_test_data()
+            .replace("\n", "\r\n")
+            .replace(" = ", "=")
+            .replace(" 0.00", "    0.0");
+        let other = UpperAir::parse(&reformatted).unwrap();
+        assert_eq!(other, snd);
+        assert_eq!(other.content_hash(), hash);
+
+        // The values do.
+        let mut other = snd.clone();
+        other.temperature[10] = optional::some(Celsius(4.55));
+        assert_ne!(other.content_hash(), hash);
+
+        let mut other = snd.clone();
+        other.dew_point[0] = optional::none();
+        assert_ne!(other.content_hash(), hash);
+
+        let mut other = snd;
+        other.id = None;
+        assert_ne!(other.content_hash(), hash);
+    }
+
+    #[test]
+    fn test_eq_and_clone() {
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+
+        // Missing values compare equal too, the dew point is missing at some levels.
+        assert!(snd.dew_point.iter().any(|dp| dp.is_none()));
+        assert_eq!(UpperAir::parse(get_test_data()).unwrap(), snd);
+        assert_eq!(snd.clone(), snd);
+
+        let mut other = snd.clone();
+        other.temperature[0] = optional::none();
+        assert_ne!(other, snd);
+    }
+
+    #[test]
+    fn test_parse_without_indexes() {
+        let full = get_test_data();
+        let start = full.find("SHOW").unwrap();
+        let end = full.find("PRES").unwrap();
+        let text = format!("{}{}", &full[..start], &full[end..]);
+
+        let snd = UpperAir::parse(&text).unwrap();
+        let expected = UpperAir::parse(full).unwrap();
+
+        assert_eq!(snd.num, 727730);
+        assert!(snd.show.is_none());
+        assert!(snd.cape.is_none());
+        assert!(snd.brch.is_none());
+        assert_eq!(snd.pressure.len(), 60);
+        assert_eq!(snd.pressure, expected.pressure);
+        assert_eq!(snd.height, expected.height);
+
+        // The header doesn't have to start with PRES.
+        use metfor::Quantity;
+        let text = text.replacen("PRES TMPC", "TMPC PRES", 1);
+        let snd = UpperAir::parse(&text).unwrap();
+        assert!(snd.show.is_none());
+        assert_eq!(snd.pressure.len(), 60);
+        assert_eq!(
+            snd.pressure
+                .iter()
+                .map(|p| p.map_t(|p| p.unpack()))
+                .collect::<Vec<_>>(),
+            expected
+                .temperature
+                .iter()
+                .map(|t| t.map_t(|t| t.unpack()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_parse_crlf() {
+        let lf = UpperAir::parse(get_test_data()).unwrap();
+        let crlf = UpperAir::parse(&get_test_data().replace('\n', "\r\n")).unwrap();
+        assert_eq!(format!("{:?}", lf), format!("{:?}", crlf));
+    }
+
+    #[test]
+    fn test_display() {
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+        assert_eq!(
+            snd.to_string(),
+            "727730 KMSO 2017-04-01 01:00Z: 60 levels from 867.2 hPa to 7.6 hPa, CAPE: yes, CIN: yes"
+        );
+
+        let snd = UpperAir {
+            id: None,
+            cins: optional::none(),
+            ..snd
+        };
+        assert_eq!(
+            snd.to_string(),
+            "727730 -- 2017-04-01 01:00Z: 60 levels from 867.2 hPa to 7.6 hPa, CAPE: yes, CIN: no"
+        );
+    }
+
     #[test]
     fn test_parse() {
         use chrono::NaiveDate;
@@ -289,7 +1730,10 @@ mod test {
         assert_eq!(snd.num, 727730);
         assert_eq!(
             snd.valid_time,
-            NaiveDate::from_ymd(2017, 4, 1).and_hms(1, 0, 0)
+            NaiveDate::from_ymd_opt(2017, 4, 1)
+                .unwrap()
+                .and_hms_opt(1, 0, 0)
+                .unwrap()
         );
         assert_eq!(snd.lead_time, 1);
         assert_eq!(snd.lat, some(46.87));
@@ -337,4 +1781,347 @@ mod test {
         assert_eq!(snd.height.len(), 60);
         assert_eq!(snd.cloud_fraction.len(), 60);
     }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_round_trip() {
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+
+        let json = serde_json::to_string(&snd).unwrap();
+        let back: UpperAir = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(back.num, snd.num);
+        assert_eq!(back.valid_time, snd.valid_time);
+        assert_eq!(back.id, snd.id);
+        assert_eq!(back.cape, snd.cape);
+        assert!(back.eqlv.is_none());
+        assert!(back.lfc.is_none());
+
+        assert_eq!(back.pressure.len(), 60);
+        assert_eq!(back.pressure, snd.pressure);
+        assert_eq!(back.temperature, snd.temperature);
+        assert_eq!(back.wind, snd.wind);
+        assert_eq!(back.height, snd.height);
+        assert_eq!(back.cloud_fraction, snd.cloud_fraction);
+        assert!(back.validate().is_ok());
+
+        // Nothing was lost, including which values were missing.
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+    }
+
+    #[test]
+    fn test_init_time() {
+        use chrono::NaiveDate;
+
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+        assert_eq!(
+            snd.init_time(),
+            NaiveDate::from_ymd_opt(2017, 4, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        );
+
+        let test_data = get_test_data()
+            .replace("TIME = 170401/0100", "TIME = 170404/1200")
+            .replace("STIM = 1", "STIM = 84");
+        let snd = UpperAir::parse(&test_data).unwrap();
+        assert_eq!(snd.lead_time, 84);
+        assert_eq!(
+            snd.init_time(),
+            NaiveDate::from_ymd_opt(2017, 4, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_has_convective_indices() {
+        use optional::some;
+
+        // CAPE is 0.00, but the LFC and EL are missing.
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+        assert!(snd.cape.is_some());
+        assert!(!snd.has_convective_indices());
+
+        let snd = UpperAir {
+            eqlv: some(HectoPascal(500.0)),
+            ..snd
+        };
+        assert!(snd.has_convective_indices());
+
+        let snd = UpperAir {
+            cape: optional::none(),
+            ..snd
+        };
+        assert!(!snd.has_convective_indices());
+    }
+
+    #[test]
+    fn test_lead_duration() {
+        let test_data = get_test_data()
+            .replace("TIME = 170401/0100", "TIME = 170404/1200")
+            .replace("STIM = 1", "STIM = 84");
+        let snd = UpperAir::parse(&test_data).unwrap();
+        assert_eq!(snd.lead_duration(), Some(chrono::Duration::hours(84)));
+
+        let snd = UpperAir {
+            lead_time: -9999,
+            ..snd
+        };
+        assert_eq!(snd.lead_duration(), None);
+    }
+
+    #[test]
+    fn test_melting_refreezing_layers() {
+        use optional::some;
+
+        // Cold at the surface with a warm nose aloft, the classic freezing rain profile.
+        let mut snd = UpperAir::parse(get_test_data()).unwrap();
+        snd.temperature = [-4.0, -2.0, 2.0, 4.0, 2.0, -2.0, -10.0]
+            .iter()
+            .map(|&t| some(Celsius(t)))
+            .collect();
+        snd.height = [1000.0, 1200.0, 1400.0, 1600.0, 1800.0, 2000.0, 3000.0]
+            .iter()
+            .map(|&h| some(Meters(h)))
+            .collect();
+
+        assert_eq!(
+            snd.melting_refreezing_layers(),
+            vec![
+                (Meters(1000.0), Meters(1300.0), false),
+                (Meters(1300.0), Meters(1900.0), true),
+                (Meters(1900.0), Meters(3000.0), false),
+            ]
+        );
+
+        // A missing level is skipped.
+        snd.temperature[2] = optional::none();
+        assert_eq!(snd.melting_refreezing_layers().len(), 3);
+
+        // No profile, no layers.
+        snd.height.clear();
+        assert!(snd.melting_refreezing_layers().is_empty());
+    }
+
+    #[test]
+    fn test_pressure_range() {
+        use optional::none;
+
+        let mut snd = UpperAir::parse(get_test_data()).unwrap();
+        assert_eq!(
+            snd.pressure_range(),
+            Some((HectoPascal(867.2), HectoPascal(7.6)))
+        );
+
+        // Missing levels are skipped.
+        let last = snd.pressure.len() - 1;
+        snd.pressure[0] = none();
+        snd.pressure[last] = none();
+        assert_eq!(
+            snd.pressure_range(),
+            Some((HectoPascal(863.5), HectoPascal(18.7)))
+        );
+
+        snd.pressure = vec![none(); 3];
+        assert_eq!(snd.pressure_range(), None);
+    }
+
+    #[test]
+    fn test_freezing_levels() {
+        use optional::some;
+
+        // One freezing level between 787.0 hPa (0.64C) and 778.2 hPa (-0.16C).
+        let mut snd = UpperAir::parse(get_test_data()).unwrap();
+        let levels = snd.freezing_levels();
+        assert_eq!(levels.len(), 1);
+
+        let (HectoPascal(p), Meters(h)) = levels[0];
+        assert!((p - (787.0 - 0.8 * 8.8)).abs() < 1.0e-9);
+        assert!((h - (2143.23 + 0.8 * (2233.47 - 2143.23))).abs() < 1.0e-9);
+
+        // All below freezing.
+        snd.temperature = snd
+            .temperature
+            .iter()
+            .map(|_| some(Celsius(-5.0)))
+            .collect();
+        assert!(snd.freezing_levels().is_empty());
+    }
+
+    #[test]
+    fn test_wet_bulb_zero() {
+        use optional::{none, some};
+
+        // Between 787.0 hPa (0.15C) and 778.2 hPa (-0.39C), and the wet bulb is missing aloft.
+        let mut snd = UpperAir::parse(get_test_data()).unwrap();
+        assert!(snd.wet_bulb.iter().any(|wb| wb.is_none()));
+
+        let Meters(h) = snd.wet_bulb_zero().unwrap();
+        let expected = 2143.23 + 0.15 / 0.54 * (2233.47 - 2143.23);
+        assert!((h - expected).abs() < 1.0e-9);
+
+        // With a warm layer aloft, it is the top crossing.
+        snd.wet_bulb[20] = some(Celsius(1.0));
+        assert!(snd.wet_bulb_zero().unwrap() > Meters(h));
+
+        snd.wet_bulb = vec![none(); snd.pressure.len()];
+        assert!(snd.wet_bulb_zero().is_none());
+    }
+
+    #[test]
+    fn test_lapse_rate() {
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+        let at = |p, field| snd.value_at_pressure(HectoPascal(p), field).unwrap();
+
+        let expected = (at(700.0, ProfileField::Temperature)
+            - at(500.0, ProfileField::Temperature))
+            / (at(500.0, ProfileField::Height) - at(700.0, ProfileField::Height))
+            * 1000.0;
+        let lapse_rate = snd
+            .lapse_rate(HectoPascal(700.0), HectoPascal(500.0))
+            .unwrap();
+        assert!((lapse_rate - expected).abs() < 1.0e-9);
+        assert!(lapse_rate > 0.0 && lapse_rate < 9.8);
+
+        // Below the bottom of the sounding.
+        assert!(snd
+            .lapse_rate(HectoPascal(900.0), HectoPascal(500.0))
+            .is_none());
+    }
+
+    #[test]
+    fn test_value_at_pressure() {
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+
+        let expected = |p: f64, (p0, t0): (f64, f64), (p1, t1): (f64, f64)| {
+            t0 + (t1 - t0) * (p.ln() - p0.ln()) / (p1.ln() - p0.ln())
+        };
+
+        let t850 = snd
+            .value_at_pressure(HectoPascal(850.0), ProfileField::Temperature)
+            .unwrap();
+        assert!((t850 - expected(850.0, (852.2, 6.54), (848.3, 6.14))).abs() < 1.0e-9);
+
+        let t500 = snd
+            .value_at_pressure(HectoPascal(500.0), ProfileField::Temperature)
+            .unwrap();
+        assert!((t500 - expected(500.0, (504.2, -15.76), (478.1, -19.06))).abs() < 1.0e-9);
+
+        // On a level
+        assert_eq!(
+            snd.value_at_pressure(HectoPascal(852.2), ProfileField::Height),
+            optional::some(1496.70)
+        );
+
+        // Below the bottom of the sounding
+        assert!(snd
+            .value_at_pressure(HectoPascal(900.0), ProfileField::Temperature)
+            .is_none());
+
+        // The dew point is missing at 151.1 hPa.
+        assert!(snd
+            .value_at_pressure(HectoPascal(160.0), ProfileField::DewPoint)
+            .is_none());
+
+        // There is no mixing ratio in this sounding.
+        assert!(snd
+            .value_at_pressure(HectoPascal(500.0), ProfileField::MixingRatio)
+            .is_none());
+    }
+
+    #[test]
+    fn test_profile_as_f64_nan() {
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+
+        let pressure = snd.profile_as_f64_nan(ProfileField::Pressure);
+        let dew_point = snd.profile_as_f64_nan(ProfileField::DewPoint);
+        assert_eq!(pressure.len(), snd.pressure.len());
+        assert_eq!(dew_point.len(), snd.dew_point.len());
+
+        for (val, expected) in dew_point.iter().zip(&snd.dew_point) {
+            match expected.into_option() {
+                Some(expected) => assert_eq!(*val, expected.0),
+                None => assert!(val.is_nan()),
+            }
+        }
+
+        // The dew point is missing at 151.1 hPa.
+        let level = pressure.iter().position(|&p| p == 151.1).unwrap();
+        assert!(dew_point[level].is_nan());
+        assert!(!snd.profile_as_f64_nan(ProfileField::Temperature)[level].is_nan());
+
+        // There is no mixing ratio in this sounding.
+        assert!(snd
+            .profile_as_f64_nan(ProfileField::MixingRatio)
+            .iter()
+            .all(|val| val.is_nan()));
+    }
+
+    #[test]
+    fn test_at_mandatory_levels() {
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+
+        // The sounding starts at 867.2 hPa, so 1000 and 925 hPa are below ground.
+        let levels = snd.at_mandatory_levels();
+        let pressures: Vec<f64> = levels.iter().map(|&(HectoPascal(p), ..)| p).collect();
+        assert_eq!(pressures, vec![850.0, 700.0, 500.0, 300.0, 250.0, 200.0]);
+
+        let (p, t, td, wind) = levels[2];
+        assert_eq!(p, HectoPascal(500.0));
+        assert_eq!(
+            t.map_t(|Celsius(t)| t),
+            snd.value_at_pressure(p, ProfileField::Temperature)
+        );
+        assert_eq!(
+            td.map_t(|Celsius(td)| td),
+            snd.value_at_pressure(p, ProfileField::DewPoint)
+        );
+        assert_eq!(
+            wind.map_t(|wind| wind.speed.0),
+            snd.value_at_pressure(p, ProfileField::Wind)
+        );
+        assert!(t.is_some() && td.is_some() && wind.is_some());
+    }
+
+    #[test]
+    fn test_align_to() {
+        let snd = UpperAir::parse(get_test_data()).unwrap();
+
+        // Make a coarser copy with every other level.
+        fn every_other<T: Copy>(vals: &[T]) -> Vec<T> {
+            vals.iter().step_by(2).cloned().collect()
+        }
+        let mut coarse = UpperAir::parse(get_test_data()).unwrap();
+        coarse.pressure = every_other(&coarse.pressure);
+        coarse.temperature = every_other(&coarse.temperature);
+        coarse.wet_bulb = every_other(&coarse.wet_bulb);
+        coarse.dew_point = every_other(&coarse.dew_point);
+        coarse.theta_e = every_other(&coarse.theta_e);
+        coarse.wind = every_other(&coarse.wind);
+        coarse.omega = every_other(&coarse.omega);
+        coarse.height = every_other(&coarse.height);
+        coarse.cloud_fraction = every_other(&coarse.cloud_fraction);
+        coarse.mixing_ratio = every_other(&coarse.mixing_ratio);
+        assert_eq!(coarse.pressure.len(), 30);
+
+        let (fine, coarse) = snd.align_to(&coarse);
+        assert!(fine.validate().is_ok());
+        assert!(coarse.validate().is_ok());
+
+        // The coarse levels are a subset of the fine ones, so the union is the fine levels.
+        assert_eq!(fine.pressure.len(), 60);
+        assert_eq!(coarse.pressure.len(), 60);
+        assert_eq!(fine.pressure, snd.pressure);
+        assert_eq!(fine.temperature, snd.temperature);
+        assert_eq!(coarse.temperature[0], snd.temperature[0]);
+        assert_eq!(coarse.temperature[2], snd.temperature[2]);
+
+        // The bottom pressure of the coarse sounding is the same as the fine one, but it
+        // doesn't extend to the top level of the fine one.
+        assert!(coarse.temperature[59].is_none());
+        assert!(coarse.temperature[1].is_some());
+    }
 }
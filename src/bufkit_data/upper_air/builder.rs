@@ -0,0 +1,260 @@
+//! Build an `UpperAir` in code instead of parsing it from text.
+
+use super::UpperAir;
+use crate::error::BufkitFileError;
+use chrono::NaiveDateTime;
+use metfor::{Celsius, HectoPascal, Kelvin, Knots, Meters, PaPS, WindSpdDir};
+use optional::{none, Optioned};
+use std::sync::Arc;
+
+/// Assemble an `UpperAir` one piece at a time, e.g. for synthetic test soundings.
+///
+/// Everything not set is missing, or empty for the profiles. The profile methods take anything
+/// that iterates over values or `Optioned` values, so missing levels are allowed.
+#[derive(Debug)]
+pub struct UpperAirBuilder {
+    snd: UpperAir,
+}
+
+impl UpperAirBuilder {
+    /// Start building a sounding valid at `valid_time`.
+    pub fn new(valid_time: NaiveDateTime) -> Self {
+        UpperAirBuilder {
+            snd: UpperAir {
+                num: 0,
+                valid_time,
+                lead_time: 0,
+                id: None,
+                lat: none(),
+                lon: none(),
+                elevation: none(),
+                show: none(),
+                li: none(),
+                swet: none(),
+                kinx: none(),
+                lclp: none(),
+                pwat: none(),
+                totl: none(),
+                cape: none(),
+                lclt: none(),
+                cins: none(),
+                eqlv: none(),
+                lfc: none(),
+                brch: none(),
+                pressure: vec![],
+                temperature: vec![],
+                wet_bulb: vec![],
+                dew_point: vec![],
+                theta_e: vec![],
+                wind: vec![],
+                omega: vec![],
+                height: vec![],
+                cloud_fraction: vec![],
+                mixing_ratio: vec![],
+            },
+        }
+    }
+
+    /// Set the station number and id.
+    pub fn with_station(mut self, num: i32, id: &str) -> Self {
+        self.snd.num = num;
+        self.snd.id = Some(Arc::from(id));
+        self
+    }
+
+    /// Set the latitude, longitude, and elevation of the station.
+    pub fn with_location(mut self, lat: f64, lon: f64, elevation: Meters) -> Self {
+        self.snd.lat = lat.into();
+        self.snd.lon = lon.into();
+        self.snd.elevation = elevation.into();
+        self
+    }
+
+    /// Set the forecast lead time in hours.
+    pub fn with_lead_time(mut self, lead_time: i32) -> Self {
+        self.snd.lead_time = lead_time;
+        self
+    }
+
+    /// Set the pressure profile.
+    pub fn with_pressure<I, T>(mut self, vals: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Optioned<HectoPascal>>,
+    {
+        self.snd.pressure = collect(vals);
+        self
+    }
+
+    /// Set the temperature profile.
+    pub fn with_temperature<I, T>(mut self, vals: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Optioned<Celsius>>,
+    {
+        self.snd.temperature = collect(vals);
+        self
+    }
+
+    /// Set the wet bulb profile.
+    pub fn with_wet_bulb<I, T>(mut self, vals: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Optioned<Celsius>>,
+    {
+        self.snd.wet_bulb = collect(vals);
+        self
+    }
+
+    /// Set the dew point profile.
+    pub fn with_dew_point<I, T>(mut self, vals: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Optioned<Celsius>>,
+    {
+        self.snd.dew_point = collect(vals);
+        self
+    }
+
+    /// Set the equivalent potential temperature profile.
+    pub fn with_theta_e<I, T>(mut self, vals: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Optioned<Kelvin>>,
+    {
+        self.snd.theta_e = collect(vals);
+        self
+    }
+
+    /// Set the wind profile.
+    pub fn with_wind<I, T>(mut self, vals: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Optioned<WindSpdDir<Knots>>>,
+    {
+        self.snd.wind = collect(vals);
+        self
+    }
+
+    /// Set the pressure vertical velocity profile.
+    pub fn with_omega<I, T>(mut self, vals: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Optioned<PaPS>>,
+    {
+        self.snd.omega = collect(vals);
+        self
+    }
+
+    /// Set the height profile.
+    pub fn with_height<I, T>(mut self, vals: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Optioned<Meters>>,
+    {
+        self.snd.height = collect(vals);
+        self
+    }
+
+    /// Set the cloud fraction profile, as fractions from 0 to 1.
+    pub fn with_cloud_fraction<I, T>(mut self, vals: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Optioned<f64>>,
+    {
+        self.snd.cloud_fraction = collect(vals);
+        self
+    }
+
+    /// Set the mixing ratio profile.
+    pub fn with_mixing_ratio<I, T>(mut self, vals: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Optioned<f64>>,
+    {
+        self.snd.mixing_ratio = collect(vals);
+        self
+    }
+
+    /// Finish the sounding, checking it with `UpperAir::validate`.
+    ///
+    /// This fails if there is no pressure profile, or if any other profile that was set has a
+    /// different length than the pressure profile.
+    pub fn build(self) -> Result<UpperAir, BufkitFileError> {
+        self.snd.validate()?;
+        Ok(self.snd)
+    }
+}
+
+fn collect<I, T, U>(vals: I) -> Vec<Optioned<U>>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<Optioned<U>>,
+    U: optional::Noned + Copy,
+{
+    vals.into_iter().map(Into::into).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDate;
+    use optional::some;
+
+    fn valid_time() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2017, 4, 1)
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .unwrap()
+    }
+
+    fn three_levels() -> UpperAirBuilder {
+        UpperAirBuilder::new(valid_time())
+            .with_station(727730, "KMSO")
+            .with_location(46.92, -114.08, Meters(972.0))
+            .with_pressure(vec![
+                HectoPascal(900.0),
+                HectoPascal(850.0),
+                HectoPascal(700.0),
+            ])
+            .with_temperature(vec![Celsius(10.0), Celsius(6.0), Celsius(-4.0)])
+            .with_dew_point(vec![some(Celsius(2.0)), none(), some(Celsius(-12.0))])
+            .with_height(vec![Meters(1000.0), Meters(1450.0), Meters(3000.0)])
+    }
+
+    #[test]
+    fn test_build() {
+        let snd = three_levels().build().unwrap();
+
+        assert!(snd.validate().is_ok());
+        assert_eq!(snd.pressure.len(), 3);
+        assert_eq!(snd.temperature[1], some(Celsius(6.0)));
+        assert!(snd.dew_point[1].is_none());
+        assert!(snd.wind.is_empty());
+        assert_eq!(snd.id.as_deref(), Some("KMSO"));
+
+        // It survives a round trip through bufkit text.
+        let round_trip = UpperAir::parse(&snd.to_bufkit_string()).unwrap();
+        assert_eq!(format!("{:?}", round_trip), format!("{:?}", snd));
+    }
+
+    #[test]
+    fn test_build_length_mismatch() {
+        let result = three_levels()
+            .with_wind(vec![WindSpdDir {
+                speed: Knots(10.0),
+                direction: 270.0,
+            }])
+            .build();
+
+        assert_eq!(
+            result.unwrap_err(),
+            BufkitFileError::ProfileLengthMismatch {
+                expected: 3,
+                found: 1,
+            }
+        );
+
+        let result = UpperAirBuilder::new(valid_time()).build();
+        assert_eq!(result.unwrap_err(), BufkitFileError::EmptyProfile);
+    }
+}
@@ -1,11 +1,13 @@
 //! Parses the string representing the upper air indexes from a bufkit file.
 
 use crate::error::*;
+use crate::parse_util::ParseOptions;
 use metfor::{Celsius, CelsiusDiff, HectoPascal, JpKg, Kelvin, Mm};
 use optional::{none, Optioned};
 
 /// Several stability indexes.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Indexes {
     pub show: Optioned<CelsiusDiff>, // Showalter index
     pub li: Optioned<CelsiusDiff>,   // Lifted index
@@ -23,7 +25,7 @@ pub struct Indexes {
 }
 
 impl Indexes {
-    pub fn parse(src: &str) -> Result<Indexes, BufkitFileError> {
+    pub fn parse(src: &str, options: &ParseOptions) -> Result<Indexes, BufkitFileError> {
         // This method assumes that these values are ALWAYS in this order. If it turns out that
         // they are not, it will probably error by using a default value, which is the missing
         // value! The easy fix would be to replace head with src in all of the parse_f64 function
@@ -45,19 +47,19 @@ impl Indexes {
 
         use crate::parse_util::parse_f64;
 
-        let (show, head) = parse_f64(src, "SHOW").unwrap_or((none(), src));
-        let (lift, head) = parse_f64(head, "LIFT").unwrap_or((none(), head));
-        let (swet, head) = parse_f64(head, "SWET").unwrap_or((none(), head));
-        let (kinx, head) = parse_f64(head, "KINX").unwrap_or((none(), head));
-        let (lclp, head) = parse_f64(head, "LCLP").unwrap_or((none(), head));
-        let (pwat, head) = parse_f64(head, "PWAT").unwrap_or((none(), head));
-        let (totl, head) = parse_f64(head, "TOTL").unwrap_or((none(), head));
-        let (cape, head) = parse_f64(head, "CAPE").unwrap_or((none(), head));
-        let (lclt, head) = parse_f64(head, "LCLT").unwrap_or((none(), head));
-        let (cins, head) = parse_f64(head, "CINS").unwrap_or((none(), head));
-        let (eqlv, head) = parse_f64(head, "EQLV").unwrap_or((none(), head));
-        let (lfct, head) = parse_f64(head, "LFCT").unwrap_or((none(), head));
-        let (brch, _) = parse_f64(head, "BRCH").unwrap_or((none(), head));
+        let (show, head) = parse_f64(src, "SHOW", options).unwrap_or((none(), src));
+        let (lift, head) = parse_f64(head, "LIFT", options).unwrap_or((none(), head));
+        let (swet, head) = parse_f64(head, "SWET", options).unwrap_or((none(), head));
+        let (kinx, head) = parse_f64(head, "KINX", options).unwrap_or((none(), head));
+        let (lclp, head) = parse_f64(head, "LCLP", options).unwrap_or((none(), head));
+        let (pwat, head) = parse_f64(head, "PWAT", options).unwrap_or((none(), head));
+        let (totl, head) = parse_f64(head, "TOTL", options).unwrap_or((none(), head));
+        let (cape, head) = parse_f64(head, "CAPE", options).unwrap_or((none(), head));
+        let (lclt, head) = parse_f64(head, "LCLT", options).unwrap_or((none(), head));
+        let (cins, head) = parse_f64(head, "CINS", options).unwrap_or((none(), head));
+        let (eqlv, head) = parse_f64(head, "EQLV", options).unwrap_or((none(), head));
+        let (lfct, head) = parse_f64(head, "LFCT", options).unwrap_or((none(), head));
+        let (brch, _) = parse_f64(head, "BRCH", options).unwrap_or((none(), head));
 
         Ok(Indexes {
             show: show.map_t(CelsiusDiff),
@@ -87,7 +89,7 @@ fn test_indexes_parse() {
         LCLT = 272.88 CINS = 0.00 EQLV = -9999.00 LFCT = -9999.00
         BRCH = 0.00";
 
-    let indexes = Indexes::parse(&test_data);
+    let indexes = Indexes::parse(test_data, &ParseOptions::default());
     println!("indexes: {:?}", indexes);
 
     let Indexes {
@@ -126,7 +128,7 @@ fn test_indexes_parse() {
         LCLT = 273.49 CINS = 0.00 EQLV = -9999.00 LFCT = -9999.00
         BRCH = 0.00";
 
-    let indexes = Indexes::parse(&test_data);
+    let indexes = Indexes::parse(test_data, &ParseOptions::default());
     println!("indexes: {:?}", indexes);
 
     let Indexes {
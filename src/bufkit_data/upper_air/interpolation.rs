@@ -0,0 +1,152 @@
+//! Interpolate profile values to a pressure level.
+
+use metfor::{HectoPascal, Knots, Quantity, WindSpdDir, WindUV};
+use optional::{none, some, Noned, Optioned};
+
+/// Interpolate a profile to the target pressure, linearly in the natural log of pressure.
+///
+/// Returns none if the target pressure is outside the range of the profile, or if either of the
+/// levels bracketing the target pressure has a missing value.
+pub fn interpolate<T>(
+    pressure: &[Optioned<HectoPascal>],
+    values: &[Optioned<T>],
+    target_p: HectoPascal,
+) -> Optioned<T>
+where
+    T: Quantity + Noned,
+{
+    let (i0, i1, weight) = match find_bracket(pressure, target_p) {
+        Some(bracket) => bracket,
+        None => return none(),
+    };
+
+    match (values.get(i0), values.get(i1)) {
+        (Some(v0), Some(v1)) => v0.and_then(|v0| {
+            v1.map_t(|v1| T::pack(v0.unpack() + weight * (v1.unpack() - v0.unpack())))
+        }),
+        _ => none(),
+    }
+}
+
+/// Interpolate a wind profile to the target pressure, linearly in the natural log of pressure.
+///
+/// The u and v components of the wind are interpolated separately.
+pub fn interpolate_wind(
+    pressure: &[Optioned<HectoPascal>],
+    wind: &[Optioned<WindSpdDir<Knots>>],
+    target_p: HectoPascal,
+) -> Optioned<WindSpdDir<Knots>> {
+    let (u, v): (Vec<Optioned<Knots>>, Vec<Optioned<Knots>>) = wind
+        .iter()
+        .map(|wind| match wind.into_option() {
+            Some(wind) => {
+                let WindUV { u, v } = WindUV::<Knots>::from(wind);
+                (some(u), some(v))
+            }
+            None => (none(), none()),
+        })
+        .unzip();
+
+    let u = interpolate(pressure, &u, target_p);
+    let v = interpolate(pressure, &v, target_p);
+
+    u.and_then(|u| v.map_t(|v| WindSpdDir::<Knots>::from(WindUV { u, v })))
+}
+
+/// Find the indexes of the levels on either side of the target pressure, and the weight to give
+/// the second level when interpolating.
+fn find_bracket(
+    pressure: &[Optioned<HectoPascal>],
+    target_p: HectoPascal,
+) -> Option<(usize, usize, f64)> {
+    let target = target_p.unpack();
+
+    for i in 0..pressure.len() {
+        let p0 = match pressure[i].into_option() {
+            Some(p) => p.unpack(),
+            None => continue,
+        };
+
+        if (p0 - target).abs() < f64::EPSILON {
+            return Some((i, i, 0.0));
+        }
+
+        let p1 = match pressure.get(i + 1).and_then(|p| p.into_option()) {
+            Some(p) => p.unpack(),
+            None => continue,
+        };
+
+        if (p0 > target && target > p1) || (p0 < target && target < p1) {
+            let weight = (target.ln() - p0.ln()) / (p1.ln() - p0.ln());
+            return Some((i, i + 1, weight));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use metfor::{Celsius, Meters};
+
+    #[test]
+    fn test_interpolate() {
+        let pressure = vec![
+            some(HectoPascal(1000.0)),
+            some(HectoPascal(900.0)),
+            some(HectoPascal(800.0)),
+            some(HectoPascal(700.0)),
+        ];
+        let temperature = vec![
+            some(Celsius(20.0)),
+            some(Celsius(10.0)),
+            none(),
+            some(Celsius(0.0)),
+        ];
+        let height = vec![
+            some(Meters(0.0)),
+            some(Meters(100.0)),
+            some(Meters(200.0)),
+            some(Meters(300.0)),
+        ];
+
+        // Exactly on a level
+        assert_eq!(
+            interpolate(&pressure, &temperature, HectoPascal(900.0)),
+            some(Celsius(10.0))
+        );
+
+        // Interpolated in log(p)
+        let weight = (950.0f64.ln() - 1000.0f64.ln()) / (900.0f64.ln() - 1000.0f64.ln());
+        let t = interpolate(&pressure, &temperature, HectoPascal(950.0)).unwrap();
+        assert!((t.unpack() - (20.0 - 10.0 * weight)).abs() < 1.0e-9);
+
+        // Missing value on one side
+        assert!(interpolate(&pressure, &temperature, HectoPascal(850.0)).is_none());
+        assert!(interpolate(&pressure, &height, HectoPascal(850.0)).is_some());
+
+        // Out of range
+        assert!(interpolate(&pressure, &temperature, HectoPascal(1010.0)).is_none());
+        assert!(interpolate(&pressure, &temperature, HectoPascal(600.0)).is_none());
+    }
+
+    #[test]
+    fn test_interpolate_wind() {
+        let pressure = vec![some(HectoPascal(1000.0)), some(HectoPascal(900.0))];
+        let wind = vec![
+            some(WindSpdDir {
+                speed: Knots(10.0),
+                direction: 270.0,
+            }),
+            some(WindSpdDir {
+                speed: Knots(20.0),
+                direction: 270.0,
+            }),
+        ];
+
+        let mid = interpolate_wind(&pressure, &wind, HectoPascal(950.0)).unwrap();
+        assert!((mid.direction - 270.0).abs() < 1.0e-6);
+        assert!(mid.speed.unpack() > 10.0 && mid.speed.unpack() < 20.0);
+    }
+}
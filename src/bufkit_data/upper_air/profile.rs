@@ -1,12 +1,12 @@
 //! Parses the *variables* vs height/pressure, or the core part of the sounding.
 
 use crate::error::*;
-use crate::parse_util::check_missing;
-use metfor::{Celsius, HectoPascal, Kelvin, Knots, Meters, PaPS, WindSpdDir};
+use crate::parse_util::{line_of, ParseOptions};
+use metfor::{Celsius, HectoPascal, Kelvin, Knots, Meters, MetersPSec, PaPS, WindSpdDir, WindUV};
 use optional::Optioned;
-use std::error::Error;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Profile {
     pub pressure: Vec<Optioned<HectoPascal>>,   // Pressure (hPa)
     pub temperature: Vec<Optioned<Celsius>>,    // Temperature (C)
@@ -16,15 +16,34 @@ pub struct Profile {
     pub wind: Vec<Optioned<WindSpdDir<Knots>>>, // Wind speed and direction in knots
     pub omega: Vec<Optioned<PaPS>>,             // Pressure vertical velocity (Pa/sec)
     pub height: Vec<Optioned<Meters>>,          // height above MSL in meters
-    pub cloud_fraction: Vec<Optioned<f64>>,     // Cloud fraction
+    pub cloud_fraction: Vec<Optioned<f64>>,     // Cloud fraction (0 to 1)
+    pub mixing_ratio: Vec<Optioned<f64>>,       // Mixing ratio (g/kg)
 }
 
 impl Profile {
     /// Given a String or slice of characters, parse them into an Profile struct.
-    pub fn parse(src: &str) -> Result<Profile, Box<dyn Error>> {
+    ///
+    /// Errors parsing values have the line number of the value, relative to the start of `src`.
+    ///
+    /// The scratch space in `buf` can be reused between profiles.
+    pub fn parse(
+        src: &str,
+        buf: &mut ProfileBuffer,
+        options: &ParseOptions,
+    ) -> Result<Profile, BufkitFileError> {
         let (header, values) = Profile::split_header_and_values(src)?;
         let cols = Profile::get_column_indexes(header)?;
-        Profile::parse_values(values, &cols)
+        Profile::parse_values(values, &cols, buf, options)
+            .map_err(|err| err.located(line_of(src, values)))
+    }
+
+    /// Check if `src` starts with a profile header, that is if the first token is a known column
+    /// name.
+    pub(crate) fn starts_with_header(src: &str) -> bool {
+        src.split_whitespace()
+            .next()
+            .and_then(ColName::from_name)
+            .is_some()
     }
 
     /// Split the section into the header and values.
@@ -32,67 +51,87 @@ impl Profile {
         // Find the end of the header, and split into header and values.
         let header_end = src
             .find(|c| c == '-' || char::is_digit(c, 10))
-            .ok_or_else(BufkitFileError::new)?;
+            .ok_or(BufkitFileError::EmptyProfile)?;
         Ok(src.split_at(header_end))
     }
 
     /// Get the index of each column name, if it exists
+    ///
+    /// It is an error for a column to show up more than once, since the values in the second one
+    /// would be mixed in with the first. It is also an error if there is no pressure column.
     fn get_column_indexes(header: &str) -> Result<ProfileColIndexes, BufkitFileError> {
-        let cols_text = header.trim().split_whitespace();
+        let cols_text = header.split_whitespace();
 
         let mut cols: ProfileColIndexes = Default::default();
 
         for (i, val) in cols_text.enumerate() {
-            match val.trim() {
-                "PRES" => cols.names[i] = ColName::PRES,
-                "TMPC" => cols.names[i] = ColName::TMPC,
-                "TMWC" => cols.names[i] = ColName::TMWC,
-                "DWPC" => cols.names[i] = ColName::DWPC,
-                "THTE" => cols.names[i] = ColName::THTE,
-                "DRCT" => cols.names[i] = ColName::DRCT,
-                "SKNT" => cols.names[i] = ColName::SKNT,
-                "OMEG" => cols.names[i] = ColName::OMEG,
-                "CFRL" => cols.names[i] = ColName::CFRL,
-                "HGHT" => cols.names[i] = ColName::HGHT,
-                _ => return Err(BufkitFileError::new()),
+            let name = ColName::from_name(val.trim())
+                .ok_or_else(|| BufkitFileError::UnknownColumn(val.trim().to_owned()))?;
+
+            if i >= cols.names.len() || cols.names.contains(&name) {
+                return Err(BufkitFileError::DuplicateColumn(val.trim().to_owned()));
             }
+            cols.names[i] = name;
+        }
+
+        if !cols.names.contains(&ColName::PRES) {
+            return Err(BufkitFileError::MissingColumn("PRES".to_owned()));
         }
 
         Ok(cols)
     }
 
     /// Given a string slice of values and some column indexes, parse them!
-    fn parse_values(values: &str, cols: &ProfileColIndexes) -> Result<Profile, Box<dyn Error>> {
+    fn parse_values(
+        values: &str,
+        cols: &ProfileColIndexes,
+        buf: &mut ProfileBuffer,
+        options: &ParseOptions,
+    ) -> Result<Profile, BufkitFileError> {
         use std::str::FromStr;
 
-        // Current GFS soundings have 64 levels of upper air data (2017)
-        const INITIAL_CAPACITY: usize = 64;
+        // Soundings in the same file almost always have the same number of levels.
+        let capacity = buf.levels;
 
         let mut parsed_vals = Profile {
-            pressure: Vec::with_capacity(INITIAL_CAPACITY),
-            temperature: Vec::with_capacity(INITIAL_CAPACITY),
-            wet_bulb: Vec::with_capacity(INITIAL_CAPACITY),
-            dew_point: Vec::with_capacity(INITIAL_CAPACITY),
-            theta_e: Vec::with_capacity(INITIAL_CAPACITY),
-            wind: Vec::with_capacity(INITIAL_CAPACITY),
-            omega: Vec::with_capacity(INITIAL_CAPACITY),
-            height: Vec::with_capacity(INITIAL_CAPACITY),
-            cloud_fraction: Vec::with_capacity(INITIAL_CAPACITY),
+            pressure: Vec::with_capacity(capacity),
+            temperature: Vec::with_capacity(capacity),
+            wet_bulb: Vec::with_capacity(capacity),
+            dew_point: Vec::with_capacity(capacity),
+            theta_e: Vec::with_capacity(capacity),
+            wind: Vec::with_capacity(capacity),
+            omega: Vec::with_capacity(capacity),
+            height: Vec::with_capacity(capacity),
+            cloud_fraction: Vec::with_capacity(capacity),
+            mixing_ratio: Vec::with_capacity(capacity),
         };
 
-        let mut direction: Vec<Optioned<f64>> = Vec::with_capacity(INITIAL_CAPACITY);
-        let mut speed: Vec<Optioned<Knots>> = Vec::with_capacity(INITIAL_CAPACITY);
+        // A previous profile may have failed part way through.
+        let direction = &mut buf.direction;
+        let speed = &mut buf.speed;
+        let u_wind = &mut buf.u_wind;
+        let v_wind = &mut buf.v_wind;
+        direction.clear();
+        speed.clear();
+        u_wind.clear();
+        v_wind.clear();
 
         let num_cols = cols.num_cols();
-        let values = values.trim().split_whitespace();
-
-        for (i, text_val) in values.enumerate() {
+        for (i, text_val) in values.split_whitespace().enumerate() {
             use self::ColName::*;
 
-            let val = check_missing(f64::from_str(text_val)?);
+            let val = f64::from_str(text_val).map_err(|_| {
+                BufkitFileError::ParseFloat(text_val.to_owned()).located(line_of(values, text_val))
+            })?;
+            let val = options.check_missing(val);
 
             match cols.names[i % num_cols] {
-                NONE => return Err(BufkitFileError::new().into()),
+                NONE => {
+                    return Err(BufkitFileError::UnknownColumn(format!(
+                        "column {}",
+                        i % num_cols + 1
+                    )))
+                }
                 PRES => parsed_vals.pressure.push(val.map_t(HectoPascal)),
                 TMPC => parsed_vals.temperature.push(val.map_t(Celsius)),
                 TMWC => parsed_vals.wet_bulb.push(val.map_t(Celsius)),
@@ -101,14 +140,20 @@ impl Profile {
                 DRCT => direction.push(val),
                 SKNT => speed.push(val.map_t(Knots)),
                 OMEG => parsed_vals.omega.push(val.map_t(PaPS)),
-                CFRL => parsed_vals.cloud_fraction.push(val),
+                // Percent in the file, stored as a fraction like the surface cloud cover.
+                CFRL => parsed_vals
+                    .cloud_fraction
+                    .push(val.map_t(|val| val / 100.0)),
                 HGHT => parsed_vals.height.push(val.map_t(Meters)),
+                MIXR => parsed_vals.mixing_ratio.push(val),
+                UWND => u_wind.push(val.map_t(MetersPSec)),
+                VWND => v_wind.push(val.map_t(MetersPSec)),
             }
         }
 
         parsed_vals.wind = direction
-            .into_iter()
-            .zip(speed.into_iter())
+            .drain(..)
+            .zip(speed.drain(..))
             .map(|(dir_opt, spd_opt)| {
                 dir_opt.and_then(|dir| {
                     spd_opt.map_t(|spd| WindSpdDir {
@@ -119,12 +164,55 @@ impl Profile {
             })
             .collect();
 
+        // Some profiles have the wind as components instead of direction and speed.
+        if parsed_vals.wind.is_empty() {
+            parsed_vals.wind = u_wind
+                .drain(..)
+                .zip(v_wind.drain(..))
+                .map(|(u_opt, v_opt)| {
+                    u_opt.and_then(|u| v_opt.map_t(|v| WindSpdDir::<Knots>::from(WindUV { u, v })))
+                })
+                .collect();
+        }
+
+        if !parsed_vals.pressure.is_empty() {
+            buf.levels = parsed_vals.pressure.len();
+        }
+
         Ok(parsed_vals)
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Current GFS soundings have 64 levels of upper air data (2017)
+const INITIAL_CAPACITY: usize = 64;
+
+/// Scratch space for parsing profiles, reused between soundings to cut down on allocations.
+#[derive(Debug)]
+pub struct ProfileBuffer {
+    direction: Vec<Optioned<f64>>,
+    speed: Vec<Optioned<Knots>>,
+    u_wind: Vec<Optioned<MetersPSec>>,
+    v_wind: Vec<Optioned<MetersPSec>>,
+    // The number of levels in the last profile, used to size the vectors in the next one.
+    levels: usize,
+}
+
+impl Default for ProfileBuffer {
+    fn default() -> Self {
+        ProfileBuffer {
+            direction: Vec::with_capacity(INITIAL_CAPACITY),
+            speed: Vec::with_capacity(INITIAL_CAPACITY),
+            u_wind: Vec::new(),
+            v_wind: Vec::new(),
+            levels: INITIAL_CAPACITY,
+        }
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 enum ColName {
+    #[default]
     NONE,
     PRES,
     TMPC,
@@ -136,17 +224,37 @@ enum ColName {
     OMEG,
     CFRL,
     HGHT,
+    MIXR,
+    UWND,
+    VWND,
 }
 
-impl Default for ColName {
-    fn default() -> ColName {
-        ColName::NONE
+impl ColName {
+    fn from_name(name: &str) -> Option<ColName> {
+        match name {
+            "PRES" => Some(ColName::PRES),
+            "TMPC" => Some(ColName::TMPC),
+            "TMWC" => Some(ColName::TMWC),
+            "DWPC" => Some(ColName::DWPC),
+            "THTE" => Some(ColName::THTE),
+            "DRCT" => Some(ColName::DRCT),
+            "SKNT" => Some(ColName::SKNT),
+            "OMEG" => Some(ColName::OMEG),
+            "CFRL" => Some(ColName::CFRL),
+            "HGHT" => Some(ColName::HGHT),
+            "MIXR" => Some(ColName::MIXR),
+            "UWND" => Some(ColName::UWND),
+            "VWND" => Some(ColName::VWND),
+            _ => None,
+        }
     }
 }
 
+/// The column names in the order they are in the header, so each value in a row can be routed
+/// to its profile no matter what order the producer wrote them in.
 #[derive(Debug, Default)]
 pub struct ProfileColIndexes {
-    names: [ColName; 10],
+    names: [ColName; 13],
 }
 
 impl ProfileColIndexes {
@@ -175,7 +283,12 @@ mod test {
                      906.70 10.54 6.12 1.52 305.69 270.00 2.14 -2.00 994.01
                      901.50 10.04 5.79 1.32 305.54 274.76 2.33 -2.00 1041.87";
 
-        let upper_air = Profile::parse(test_data).unwrap();
+        let upper_air = Profile::parse(
+            test_data,
+            &mut ProfileBuffer::default(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
 
         println!("upper_air: {:?}", upper_air);
 
@@ -229,6 +342,39 @@ mod test {
     // OMEG - Vertical velocity (Pa/s)
     // CFRL - Fractional cloud coverage (percent)
     // HGHT - Height of pressure level (m)
+    // UWND - East-west wind component (m/s), instead of DRCT and SKNT
+    // VWND - North-south wind component (m/s), instead of DRCT and SKNT
+
+    #[test]
+    fn test_parse_wind_components() {
+        use metfor::Quantity;
+
+        let test_data = "PRES TMPC UWND VWND HGHT
+                     906.70 10.54 0.00 -5.00 994.01
+                     901.50 10.04 10.00 0.00 1041.87
+                     896.30 9.54 -9999.00 1.00 1089.94";
+
+        let upper_air = Profile::parse(
+            test_data,
+            &mut ProfileBuffer::default(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(upper_air.wind.len(), 3);
+
+        // A wind from the north.
+        let wind = upper_air.wind[0].unwrap();
+        assert!((wind.direction - 360.0).abs() < 1.0e-6 || wind.direction.abs() < 1.0e-6);
+        assert!((wind.speed.unpack() - Knots::from(MetersPSec(5.0)).unpack()).abs() < 1.0e-6);
+
+        // A wind from the west.
+        let wind = upper_air.wind[1].unwrap();
+        assert!((wind.direction - 270.0).abs() < 1.0e-6);
+        assert!((wind.speed.unpack() - Knots::from(MetersPSec(10.0)).unpack()).abs() < 1.0e-6);
+
+        assert!(upper_air.wind[2].is_none());
+    }
 
     #[test]
     fn test_split_header_and_values() {
@@ -276,10 +422,18 @@ mod test {
                      901.50 10.04 5.79 1.32 305.54 274.76 2.33 -2.00 1041.87";
 
         let cols = ProfileColIndexes {
-            names: [PRES, TMPC, TMWC, DWPC, THTE, DRCT, SKNT, OMEG, HGHT, NONE],
+            names: [
+                PRES, TMPC, TMWC, DWPC, THTE, DRCT, SKNT, OMEG, HGHT, NONE, NONE, NONE, NONE,
+            ],
         };
 
-        let upper_air = Profile::parse_values(test_data, &cols).unwrap();
+        let upper_air = Profile::parse_values(
+            test_data,
+            &cols,
+            &mut ProfileBuffer::default(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
 
         println!("upper_air: {:?}", upper_air);
 
@@ -322,4 +476,110 @@ mod test {
             vec![some(Meters(994.01)), some(Meters(1041.87))]
         );
     }
+
+    #[test]
+    fn test_bad_column_headers() {
+        // Duplicate pressure column.
+        let test_data = "PRES TMPC TMWC DWPC THTE DRCT SKNT OMEG PRES
+                     906.70 10.54 6.12 1.52 305.69 270.00 2.14 -2.00 906.70";
+        assert!(Profile::parse(
+            test_data,
+            &mut ProfileBuffer::default(),
+            &ParseOptions::default()
+        )
+        .is_err());
+
+        // No pressure column.
+        let test_data = "TMPC TMWC DWPC THTE DRCT SKNT OMEG HGHT
+                     10.54 6.12 1.52 305.69 270.00 2.14 -2.00 994.01";
+        assert!(Profile::parse(
+            test_data,
+            &mut ProfileBuffer::default(),
+            &ParseOptions::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_reordered_header() {
+        let test_data = "HGHT DWPC PRES TMPC
+                     994.01 1.52 906.70 10.54
+                     1041.87 -9999.00 901.50 10.04
+                     1089.94 1.12 896.30 9.54";
+
+        let upper_air = Profile::parse(
+            test_data,
+            &mut ProfileBuffer::default(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            upper_air.pressure,
+            vec![
+                some(HectoPascal(906.7)),
+                some(HectoPascal(901.5)),
+                some(HectoPascal(896.3))
+            ]
+        );
+        assert_eq!(
+            upper_air.height,
+            vec![
+                some(Meters(994.01)),
+                some(Meters(1041.87)),
+                some(Meters(1089.94))
+            ]
+        );
+        assert_eq!(
+            upper_air.temperature,
+            vec![
+                some(Celsius(10.54)),
+                some(Celsius(10.04)),
+                some(Celsius(9.54))
+            ]
+        );
+        assert_eq!(upper_air.dew_point[0], some(Celsius(1.52)));
+        assert!(upper_air.dew_point[1].is_none());
+        assert_eq!(upper_air.dew_point[2], some(Celsius(1.12)));
+        assert!(upper_air.wet_bulb.is_empty());
+        assert!(upper_air.wind.is_empty());
+    }
+
+    #[test]
+    fn test_parse_mixing_ratio() {
+        let test_data = "PRES TMPC TMWC DWPC THTE DRCT SKNT OMEG CFRL HGHT MIXR
+                     906.70 10.54 6.12 1.52 305.69 270.00 2.14 -2.00 0.00 994.01 4.61
+                     901.50 10.04 5.79 1.32 305.54 274.76 2.33 -2.00 -9999.00 1041.87 4.57";
+
+        let upper_air = Profile::parse(
+            test_data,
+            &mut ProfileBuffer::default(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(upper_air.mixing_ratio, vec![some(4.61), some(4.57)]);
+        assert_eq!(
+            upper_air.pressure,
+            vec![some(HectoPascal(906.7)), some(HectoPascal(901.5))]
+        );
+        assert_eq!(
+            upper_air.height,
+            vec![some(Meters(994.01)), some(Meters(1041.87))]
+        );
+        assert_eq!(upper_air.cloud_fraction[0], some(0.0));
+        assert!(upper_air.cloud_fraction[1].is_none());
+
+        // Without the column there are no values.
+        let test_data = "PRES TMPC
+                     906.70 10.54";
+        assert!(Profile::parse(
+            test_data,
+            &mut ProfileBuffer::default(),
+            &ParseOptions::default()
+        )
+        .unwrap()
+        .mixing_ratio
+        .is_empty());
+    }
 }
@@ -1,32 +1,37 @@
 //! Parse the station info section of a bufkit upper air section.
 
-use crate::parse_util::{parse_f64, parse_i32, parse_kv, parse_naive_date_time};
+use crate::error::BufkitFileError;
+use crate::parse_util::{
+    parse_f64, parse_i32, parse_kv, parse_naive_date_time, ParseOptions, MISSING_I32,
+};
 use chrono::NaiveDateTime;
 use metfor::Meters;
 use optional::Optioned;
-use std::error::Error;
 
 /// Information related to the geographic location of the sounding.
-#[derive(Debug)]
-pub struct StationInfo {
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StationInfo<'a> {
     pub num: i32,                    // station number, USAF number, eg 727730
     pub valid_time: NaiveDateTime,   // valid time of sounding
     pub lead_time: i32,              // Forecast lead time in hours from model init
-    pub id: Option<String>,          // Usually a 3-4 character alphanumeric identifier.
+    pub id: Option<&'a str>,         // Usually a 3-4 character alphanumeric identifier.
     pub lat: Optioned<f64>,          // latitude
     pub lon: Optioned<f64>,          // longitude
     pub elevation: Optioned<Meters>, // elevation (m)
 }
 
-impl StationInfo {
+impl<'a> StationInfo<'a> {
     /// Given a String or slice of characters, parse them into a StationInfo struct.
-    pub fn parse(src: &str) -> Result<StationInfo, Box<dyn Error>> {
+    ///
+    /// The station id is borrowed from `src`.
+    pub fn parse(src: &'a str, options: &ParseOptions) -> Result<StationInfo<'a>, BufkitFileError> {
         // This method assumes that these values are ALWAYS in this order. If it turns out that
         // they are not, it will probably error! The easy fix would be to replace head with src
         // in all of the parse_* function calls below, at the expense of a probably slower parsing
         // function.
         //
-        // STID - Station ID (alphanumeric)
+        // STID - Station ID (alphanumeric, '_', '-', or '.')
         // STNM - 6-digit station Number
         // TIME - Valid time (UTC) in YYMMDD/HHMM numeric format
         // SLAT - Latitude (decimal degrees)
@@ -35,39 +40,28 @@ impl StationInfo {
         // STIM - Forecast hour
 
         // Get the station id
-        let (station_id, mut head) = parse_kv(
-            src,
-            "STID",
-            |c| char::is_alphanumeric(c),
-            |c| !char::is_alphanumeric(c),
-        )?;
+        let is_id_char = |c: char| c.is_alphanumeric() || c == '_' || c == '-' || c == '.';
+        let (station_id, mut head) = parse_kv(src, "STID", is_id_char, |c| !is_id_char(c))?;
 
         let station_id = if station_id == "STNM" {
             head = src;
             None
         } else {
-            Some(station_id.to_owned())
+            Some(station_id)
         };
 
         // Get station num
-        let (station_num, head) = parse_i32(head, "STNM")?;
+        let (station_num, head) = StationInfo::parse_num(head)?;
 
         // Get valid time
-        let (val_to_parse, head) = parse_kv(
-            head,
-            "TIME",
-            |c| char::is_digit(c, 10),
-            |c| !(char::is_digit(c, 10) || c == '/'),
-        )?;
-
-        let vt = parse_naive_date_time(val_to_parse)?;
+        let (vt, head) = StationInfo::parse_time(head)?;
 
         // get latitude, longitude, and elevation
-        let (lat, head) = parse_f64(head, "SLAT")?;
+        let (lat, head) = parse_f64(head, "SLAT", options)?;
 
-        let (lon, head) = parse_f64(head, "SLON")?;
+        let (lon, head) = parse_f64(head, "SLON", options)?;
 
-        let (elv, head) = parse_f64(head, "SELV")?;
+        let (elv, head) = parse_f64(head, "SELV", options)?;
 
         // get the lead time
         let (lt, _) = parse_i32(head, "STIM")?;
@@ -82,6 +76,51 @@ impl StationInfo {
             elevation: elv.map_t(Meters),
         })
     }
+
+    /// Parse only the valid time, without parsing the rest of the station info.
+    pub fn parse_valid_time(src: &str) -> Result<NaiveDateTime, BufkitFileError> {
+        StationInfo::parse_time(src).map(|(vt, _)| vt)
+    }
+
+    /// Parse only the station number, without parsing the rest of the station info.
+    ///
+    /// A blank station number is returned as the missing value, -9999.
+    pub fn parse_station_num(src: &str) -> Result<i32, BufkitFileError> {
+        StationInfo::parse_num(src).map(|(num, _)| num)
+    }
+
+    fn parse_num(src: &str) -> Result<(i32, &str), BufkitFileError> {
+        use std::str::FromStr;
+
+        // Take the whole token after the key, so a blank value is not confused with the digits
+        // in the time that follows it.
+        let (val_to_parse, head) = parse_kv(
+            src,
+            "STNM",
+            |c| !(char::is_whitespace(c) || c == '='),
+            char::is_whitespace,
+        )?;
+
+        if val_to_parse == "TIME" {
+            let head = &src[src.find("STNM").unwrap_or(0)..];
+            return Ok((MISSING_I32, head));
+        }
+
+        let val = i32::from_str(val_to_parse)
+            .map_err(|_| BufkitFileError::ParseInt(val_to_parse.to_owned()))?;
+        Ok((val, head))
+    }
+
+    fn parse_time(src: &str) -> Result<(NaiveDateTime, &str), BufkitFileError> {
+        let (val_to_parse, head) = parse_kv(
+            src,
+            "TIME",
+            |c| char::is_digit(c, 10),
+            |c| !(char::is_digit(c, 10) || c == '/'),
+        )?;
+
+        Ok((parse_naive_date_time(val_to_parse)?, head))
+    }
 }
 
 #[test]
@@ -93,7 +132,7 @@ fn test_station_info_parse() {
                      SLAT = 46.92 SLON = -114.08 SELV = 972.0
                      STIM = 0";
 
-    let si = StationInfo::parse(&test_data);
+    let si = StationInfo::parse(test_data, &ParseOptions::default());
     println!("si: {:?}", si);
 
     let StationInfo {
@@ -107,7 +146,13 @@ fn test_station_info_parse() {
     } = si.unwrap();
     assert_eq!(id, None);
     assert_eq!(num, 727730);
-    assert_eq!(valid_time, NaiveDate::from_ymd(2017, 4, 1).and_hms(0, 0, 0));
+    assert_eq!(
+        valid_time,
+        NaiveDate::from_ymd_opt(2017, 4, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    );
     assert_eq!(lead_time, 0);
     assert_eq!(lat, some(46.92));
     assert_eq!(lon, some(-114.08));
@@ -117,7 +162,7 @@ fn test_station_info_parse() {
                      SLAT = 46.87 SLON = -114.16 SELV = 1335.0
                      STIM = 84";
 
-    let si = StationInfo::parse(&test_data);
+    let si = StationInfo::parse(test_data, &ParseOptions::default());
     println!("si: {:?}", si);
 
     let StationInfo {
@@ -133,10 +178,79 @@ fn test_station_info_parse() {
     assert_eq!(num, 727730);
     assert_eq!(
         valid_time,
-        NaiveDate::from_ymd(2017, 4, 4).and_hms(12, 0, 0)
+        NaiveDate::from_ymd_opt(2017, 4, 4)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
     );
     assert_eq!(lead_time, 84);
     assert_eq!(lat, some(46.87));
     assert_eq!(lon, some(-114.16));
     assert_eq!(elevation, some(Meters(1335.0)));
 }
+
+#[test]
+fn test_station_info_parse_id_punctuation() {
+    for id in &["KMSO-1", "K_MSO", "KMSO.2"] {
+        let test_data = format!(
+            "STID = {} STNM = 727730 TIME = 170404/1200
+             SLAT = 46.87 SLON = -114.16 SELV = 1335.0
+             STIM = 84",
+            id
+        );
+
+        let si = StationInfo::parse(&test_data, &ParseOptions::default()).unwrap();
+        assert_eq!(si.id, Some(*id));
+        assert_eq!(si.num, 727730);
+    }
+}
+
+#[test]
+fn test_station_info_parse_blank_station_num() {
+    use chrono::NaiveDate;
+    use optional::some;
+
+    let test_data = "STID = KMSO STNM = TIME = 170404/1200
+                     SLAT = 46.87 SLON = -114.16 SELV = 1335.0
+                     STIM = 84";
+
+    let si = StationInfo::parse(test_data, &ParseOptions::default()).unwrap();
+    assert_eq!(si.id, Some("KMSO"));
+    assert_eq!(si.num, MISSING_I32);
+    assert_eq!(
+        si.valid_time,
+        NaiveDate::from_ymd_opt(2017, 4, 4)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    );
+    assert_eq!(si.lat, some(46.87));
+    assert_eq!(si.lon, some(-114.16));
+    assert_eq!(si.lead_time, 84);
+
+    assert_eq!(
+        StationInfo::parse_station_num(test_data).unwrap(),
+        MISSING_I32
+    );
+    assert_eq!(
+        StationInfo::parse_station_num("STID = KMSO STNM = -42 TIME = 170404/1200").unwrap(),
+        -42
+    );
+}
+
+#[test]
+fn test_station_info_parse_valid_time() {
+    use chrono::NaiveDate;
+
+    let test_data = "STID = KMSO STNM = 727730 TIME = 170404/1200
+                     SLAT = 46.87 SLON = -114.16 SELV = 1335.0
+                     STIM = 84";
+
+    assert_eq!(
+        StationInfo::parse_valid_time(test_data).unwrap(),
+        NaiveDate::from_ymd_opt(2017, 4, 4)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    );
+}
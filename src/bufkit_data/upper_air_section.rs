@@ -1,29 +1,155 @@
 //! Deals with the text and parsing of the upper section in a bufkit file.
 
-use crate::bufkit_data::upper_air::UpperAir;
-use std::error::Error;
+use crate::bufkit_data::upper_air::{ParseBuffer, UpperAir};
+use crate::error::BufkitFileError;
+use crate::parse_util::ParseOptions;
+use chrono::NaiveDateTime;
+use std::ops::Range;
+use std::sync::OnceLock;
 
 /// Represents the section of a string that represents sounding data in a bufkit file.
 pub struct UpperAirSection<'a> {
     raw_text: &'a str,
+    // The byte ranges of each sounding in raw_text, found the first time they are needed.
+    chunk_ranges: OnceLock<Vec<Range<usize>>>,
+    options: ParseOptions,
 }
 
 impl<'a> UpperAirSection<'a> {
     /// Create a new UpperAirSection.
     pub fn new(text: &'a str) -> UpperAirSection<'a> {
-        UpperAirSection { raw_text: text }
+        UpperAirSection::with_options(text, ParseOptions::default())
+    }
+
+    /// Create a new UpperAirSection that parses with `options`.
+    pub fn with_options(text: &'a str, options: ParseOptions) -> UpperAirSection<'a> {
+        UpperAirSection {
+            raw_text: text,
+            chunk_ranges: OnceLock::new(),
+            options,
+        }
+    }
+
+    /// Parse one sounding with this section's options.
+    fn parse_chunk(&self, chunk: &str) -> Result<UpperAir, BufkitFileError> {
+        UpperAir::parse_with(chunk, &mut ParseBuffer::new(self.options.clone()))
+    }
+
+    /// Get the byte ranges of the text of each sounding in the section.
+    ///
+    /// The section is only scanned the first time this is called.
+    pub fn chunk_ranges(&self) -> &[Range<usize>] {
+        self.chunk_ranges.get_or_init(|| {
+            let mut iter = self.into_iter();
+            std::iter::from_fn(|| iter.get_next_chunk())
+                .map(|chunk| {
+                    let start = chunk.as_ptr() as usize - self.raw_text.as_ptr() as usize;
+                    start..(start + chunk.len())
+                })
+                .collect()
+        })
+    }
+
+    /// Get the text of the sounding at `index`, without parsing it.
+    pub fn chunk(&self, index: usize) -> Option<&'a str> {
+        self.chunk_ranges()
+            .get(index)
+            .map(|range| &self.raw_text[range.clone()])
+    }
+
+    /// Get the text of the whole section, including the header.
+    pub fn text(&self) -> &'a str {
+        self.raw_text
+    }
+
+    /// Get the text before the first sounding, e.g. the lists of profile and index names.
+    pub fn header(&self) -> &'a str {
+        let end = self
+            .chunk_ranges()
+            .first()
+            .map_or(self.raw_text.len(), |range| range.start);
+        &self.raw_text[..end]
+    }
+
+    /// Iterate over the text of each sounding, without parsing them.
+    fn chunks(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.chunk_ranges()
+            .iter()
+            .map(move |range| &self.raw_text[range.clone()])
     }
 
     /// Validate the upper air section
-    pub fn validate_section(&self) -> Result<(), Box<dyn Error>> {
+    ///
+    /// Errors have the line number where they happened, relative to the start of the section.
+    pub fn validate_section(&self) -> Result<(), BufkitFileError> {
+        self.parse_each(|_| {})
+    }
+
+    /// Parse all of the soundings, failing on the first bad one like `validate_section`.
+    pub fn parse_all(&self) -> Result<Vec<UpperAir>, BufkitFileError> {
+        let mut all = vec![];
+        self.parse_each(|ua| all.push(ua))?;
+        Ok(all)
+    }
+
+    /// Parse all of the soundings in parallel, skipping any that fail like the iterator does.
+    #[cfg(feature = "rayon")]
+    pub fn par_parse(&self) -> Vec<UpperAir> {
+        use rayon::prelude::*;
+
+        self.chunk_ranges()
+            .par_iter()
+            .map(|range| &self.raw_text[range.clone()])
+            .filter_map(|chunk| self.parse_chunk(chunk).ok())
+            .collect()
+    }
+
+    fn parse_each<F: FnMut(UpperAir)>(&self, mut f: F) -> Result<(), BufkitFileError> {
+        use crate::parse_util::line_of;
+
         let mut iter = self.into_iter();
 
         while let Some(chunk) = iter.get_next_chunk() {
-            let ua = UpperAir::parse(chunk)?;
-            ua.validate()?;
+            let line = line_of(self.raw_text, chunk);
+            let ua =
+                UpperAir::parse_with(chunk, &mut iter.buffer).map_err(|err| err.located(line))?;
+            ua.validate().map_err(|err| err.located(line))?;
+            f(ua);
         }
         Ok(())
     }
+
+    /// Validate every sounding in the upper air section, collecting all the errors instead of
+    /// stopping at the first one.
+    ///
+    /// Each error is paired with the line where its sounding starts, which is often not the line
+    /// of the bad value in the error. Both are relative to the start of the section.
+    pub fn validate_collect(&self) -> Vec<(usize, BufkitFileError)> {
+        use crate::parse_util::line_of;
+
+        self.chunks()
+            .filter_map(|chunk| {
+                let line = line_of(self.raw_text, chunk);
+                self.parse_chunk(chunk)
+                    .and_then(|ua| ua.validate())
+                    .err()
+                    .map(|err| (line, err.located(line)))
+            })
+            .collect()
+    }
+
+    /// Iterate over the valid times of the soundings without parsing the profiles.
+    pub fn valid_times(&'a self) -> impl Iterator<Item = NaiveDateTime> + 'a {
+        self.chunks()
+            .filter_map(|chunk| UpperAir::parse_valid_time(chunk).ok())
+    }
+
+    /// Find and parse the sounding with the given valid time, without parsing the others.
+    pub fn parse_at(&self, valid_time: NaiveDateTime) -> Option<UpperAir> {
+        self.chunks()
+            .find(|chunk| UpperAir::parse_valid_time(chunk).ok() == Some(valid_time))
+            .and_then(|chunk| self.parse_chunk(chunk).ok())
+    }
 }
 
 impl<'a> IntoIterator for &'a UpperAirSection<'a> {
@@ -33,6 +159,7 @@ impl<'a> IntoIterator for &'a UpperAirSection<'a> {
     fn into_iter(self) -> Self::IntoIter {
         UpperAirIterator {
             remaining: self.raw_text,
+            buffer: ParseBuffer::new(self.options.clone()),
         }
     }
 }
@@ -42,33 +169,90 @@ impl<'a> IntoIterator for &'a UpperAirSection<'a> {
 /// If there is a parsing error, it skips the entry that caused it and moves on.
 pub struct UpperAirIterator<'a> {
     remaining: &'a str,
+    buffer: ParseBuffer,
 }
 
 impl<'a> UpperAirIterator<'a> {
     fn get_next_chunk(&mut self) -> Option<&'a str> {
-        // Cordon off the text I want
-        let next_chunk: &str;
-        if let Some(start) = self.remaining.find("STID =") {
-            let end = self.remaining[(start + 2)..]
-                .find("STID =")
-                .unwrap_or_else(|| self.remaining[(start + 2)..].len())
-                + (start + 2);
-            next_chunk = &self.remaining[start..end];
-            self.remaining = &self.remaining[end..];
-        } else {
-            return None;
+        let (next_chunk, rest) = split_next_chunk(self.remaining)?;
+        self.remaining = rest;
+        Some(next_chunk)
+    }
+
+    /// Get the text of the next sounding along with its valid time, without parsing the rest of
+    /// it.
+    ///
+    /// Soundings without a valid time that parses are skipped.
+    pub(crate) fn next_timed_chunk(&mut self) -> Option<(NaiveDateTime, &'a str)> {
+        while let Some(chunk) = self.get_next_chunk() {
+            if let Ok(valid_time) = UpperAir::parse_valid_time(chunk) {
+                return Some((valid_time, chunk));
+            }
+        }
+        None
+    }
+
+    /// Parse the text of a sounding from `next_timed_chunk`.
+    pub(crate) fn parse_chunk(&mut self, chunk: &str) -> Option<UpperAir> {
+        UpperAir::parse_with(chunk, &mut self.buffer).ok()
+    }
+
+    /// Get the valid time of the next sounding without consuming it.
+    ///
+    /// Only the valid time is parsed, so if the rest of the next sounding fails to parse, `next`
+    /// will skip it and return a later sounding.
+    pub fn peek_time(&self) -> Option<NaiveDateTime> {
+        let mut remaining = self.remaining;
+        while let Some((chunk, rest)) = split_next_chunk(remaining) {
+            if let Ok(valid_time) = UpperAir::parse_valid_time(chunk) {
+                return Some(valid_time);
+            }
+            remaining = rest;
         }
+        None
+    }
+
+    fn get_next_back_chunk(&mut self) -> Option<&'a str> {
+        // Cordon off the text I want, starting from the end
+        let start = self.remaining.rfind("STID =")?;
+        let next_chunk = &self.remaining[start..];
+        self.remaining = &self.remaining[..start];
         Some(next_chunk)
     }
 }
 
+/// Split the text of the next sounding off the front of `text`, returning it and the rest of the
+/// text after it.
+fn split_next_chunk(text: &str) -> Option<(&str, &str)> {
+    // Cordon off the text I want
+    let start = text.find("STID =")?;
+    let end = text[(start + 2)..]
+        .find("STID =")
+        .unwrap_or_else(|| text[(start + 2)..].len())
+        + (start + 2);
+    Some((&text[start..end], &text[end..]))
+}
+
 impl<'a> Iterator for UpperAirIterator<'a> {
     type Item = UpperAir;
 
     fn next(&mut self) -> Option<UpperAir> {
         // Keep trying different chunks of text while you can
         while let Some(text) = self.get_next_chunk() {
-            if let Ok(snd) = UpperAir::parse(text) {
+            if let Ok(snd) = UpperAir::parse_with(text, &mut self.buffer) {
+                return Some(snd);
+            }
+        }
+        // Ran out of text to try
+        None
+    }
+}
+
+impl<'a> DoubleEndedIterator for UpperAirIterator<'a> {
+    fn next_back(&mut self) -> Option<UpperAir> {
+        // Keep trying different chunks of text while you can
+        while let Some(text) = self.get_next_back_chunk() {
+            if let Ok(snd) = UpperAir::parse_with(text, &mut self.buffer) {
                 return Some(snd);
             }
         }
@@ -81,6 +265,60 @@ impl<'a> Iterator for UpperAirIterator<'a> {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_peek_time() {
+        let section = UpperAirSection::new(get_valid_test_data());
+        let mut iter = section.into_iter();
+
+        let mut count = 0;
+        while let Some(valid_time) = iter.peek_time() {
+            // Peeking doesn't advance the iterator.
+            assert_eq!(iter.peek_time(), Some(valid_time));
+            assert_eq!(iter.next().map(|ua| ua.valid_time), Some(valid_time));
+            count += 1;
+        }
+        assert_eq!(count, 5);
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_chunk_ranges() {
+        let section = UpperAirSection::new(get_valid_test_data());
+
+        let mut iter = section.into_iter();
+        let sequential: Vec<&str> = std::iter::from_fn(|| iter.get_next_chunk()).collect();
+        assert_eq!(sequential.len(), 5);
+
+        let ranges = section.chunk_ranges();
+        assert_eq!(ranges.len(), sequential.len());
+        for (i, chunk) in sequential.iter().enumerate() {
+            assert_eq!(section.chunk(i), Some(*chunk));
+        }
+        assert_eq!(section.chunk(5), None);
+    }
+
+    #[test]
+    fn test_reused_buffer_matches_parse() {
+        let section = UpperAirSection::new(get_valid_test_data());
+
+        let mut iter = section.into_iter();
+        let fresh: Vec<String> = std::iter::from_fn(|| iter.get_next_chunk())
+            .map(|chunk| format!("{:?}", UpperAir::parse(chunk).unwrap()))
+            .collect();
+        assert_eq!(fresh.len(), 5);
+
+        let reused: Vec<String> = section.into_iter().map(|ua| format!("{:?}", ua)).collect();
+        assert_eq!(fresh, reused);
+
+        let mut reused_back: Vec<String> = section
+            .into_iter()
+            .rev()
+            .map(|ua| format!("{:?}", ua))
+            .collect();
+        reused_back.reverse();
+        assert_eq!(fresh, reused_back);
+    }
+
     fn get_valid_test_data() -> &'static str {
         "
         SNPARM = PRES;TMPC;TMWC;DWPC;THTE;DRCT;SKNT;OMEG;HGHT
@@ -2216,15 +2454,51 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_sounding_iterator_reversed() {
+        let sounding = UpperAirSection::new(get_valid_test_data());
+
+        assert_eq!(
+            sounding
+                .into_iter()
+                .rev()
+                .map(|snd| snd.lead_time)
+                .collect::<Vec<i32>>(),
+            vec![12, 9, 6, 3, 0]
+        );
+
+        // Meet in the middle.
+        let mut iter = sounding.into_iter();
+        assert_eq!(iter.next().unwrap().lead_time, 0);
+        assert_eq!(iter.next_back().unwrap().lead_time, 12);
+        assert_eq!(
+            iter.map(|snd| snd.lead_time).collect::<Vec<i32>>(),
+            vec![3, 6, 9]
+        );
+    }
+
+    #[test]
+    fn test_valid_times() {
+        let sounding = UpperAirSection::new(get_valid_test_data());
+
+        assert_eq!(
+            sounding.valid_times().collect::<Vec<_>>(),
+            sounding
+                .into_iter()
+                .map(|snd| snd.valid_time)
+                .collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn test_validation() {
         let sounding = UpperAirSection::new(get_valid_test_data());
         assert!(sounding.validate_section().is_ok());
 
         let sounding = UpperAirSection::new(get_invalid_test_data1());
-        assert!(!sounding.validate_section().is_ok());
+        assert!(sounding.validate_section().is_err());
 
         let sounding = UpperAirSection::new(get_invalid_test_data2());
-        assert!(!sounding.validate_section().is_ok());
+        assert!(sounding.validate_section().is_err());
     }
 }
@@ -0,0 +1,338 @@
+//! Write parsed data back out as bufkit text.
+
+use super::{BufkitData, SurfaceData, UpperAir};
+use crate::parse_util::MISSING_F64;
+use chrono::NaiveDateTime;
+use metfor::{MetersPSec, Quantity, WindUV};
+use optional::{Noned, Optioned};
+use std::error::Error;
+use std::fmt::Write;
+
+/// The surface columns written by `SurfaceData::to_bufkit_string`, every column that is parsed.
+pub(crate) const SURFACE_HEADER: &str = "STN YYMMDD/HHMM PMSL PRES SKTC STC1 SNFL P01M\n\
+                                         C01M STC2 LCLD MCLD HCLD SNRA UWND VWND\n\
+                                         T2MS WXTS WXTP WXTZ WXTR USTM VSTM HLCY\n\
+                                         WSYM CDBP VSBK TD2M\n";
+
+/// The number of values or column names written on each line.
+const VALUES_PER_LINE: usize = 8;
+
+impl<'a> BufkitData<'a> {
+    /// Write all of the data back out as the text of a bufkit file.
+    ///
+    /// The header with the lists of profile and index names is copied from the original text,
+    /// then each sounding and surface record is written with `to_bufkit_string`. Soundings and
+    /// surface records that fail to parse are left out. Loading the output gives back the same
+    /// soundings as iterating over this data.
+    pub fn write_bufkit<W: std::io::Write>(&self, w: W) -> Result<(), Box<dyn Error>> {
+        self.write_bufkit_filtered(w, |_| true)
+    }
+
+    /// Get the text of a bufkit file with only the soundings and surface records valid from
+    /// `start` to `end`, inclusive.
+    ///
+    /// This is written the same way as `write_bufkit`, so the header is kept.
+    pub fn subset_by_time(&self, start: NaiveDateTime, end: NaiveDateTime) -> String {
+        let mut text = vec![];
+        self.write_bufkit_filtered(&mut text, |vt| start <= vt && vt <= end)
+            .expect("writing to memory can't fail");
+        String::from_utf8(text).expect("everything written is valid UTF-8")
+    }
+
+    /// Write the data as a bufkit file, keeping only the valid times that pass `keep`.
+    fn write_bufkit_filtered<W, F>(&self, mut w: W, keep: F) -> Result<(), Box<dyn Error>>
+    where
+        W: std::io::Write,
+        F: Fn(NaiveDateTime) -> bool,
+    {
+        w.write_all(self.upper_air.header().as_bytes())?;
+        for ua in self.upper_air().filter(|ua| keep(ua.valid_time)) {
+            writeln!(w, "{}", ua.to_bufkit_string())?;
+        }
+
+        let mut surface = SURFACE_HEADER.to_owned();
+        for sd in self.surface_data().filter(|sd| keep(sd.valid_time)) {
+            sd.write_bufkit_row(&mut surface);
+        }
+        w.write_all(surface.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+impl UpperAir {
+    /// Write the sounding as bufkit text, the same as a sounding in the upper air section of a
+    /// file.
+    ///
+    /// This includes the station info, the indexes, the column header, and the profile. Only the
+    /// profiles that have values get a column, and missing values are written as `-9999.00`.
+    /// Parsing the text gives back this sounding.
+    pub fn to_bufkit_string(&self) -> String {
+        let mut text = String::new();
+
+        // Station info
+        let _ = writeln!(
+            text,
+            "STID = {} STNM = {} TIME = {}",
+            self.id.as_deref().unwrap_or(""),
+            self.num,
+            bufkit_time(self.valid_time)
+        );
+        let _ = writeln!(
+            text,
+            "SLAT = {} SLON = {} SELV = {}",
+            value(self.lat),
+            value(self.lon),
+            value(self.elevation)
+        );
+        let _ = writeln!(text, "STIM = {}", self.lead_time);
+        text.push('\n');
+
+        // Indexes
+        let _ = writeln!(
+            text,
+            "SHOW = {} LIFT = {} SWET = {} KINX = {}",
+            value(self.show),
+            value(self.li),
+            value(self.swet),
+            value(self.kinx)
+        );
+        let _ = writeln!(
+            text,
+            "LCLP = {} PWAT = {} TOTL = {} CAPE = {}",
+            value(self.lclp),
+            value(self.pwat),
+            value(self.totl),
+            value(self.cape)
+        );
+        let _ = writeln!(
+            text,
+            "LCLT = {} CINS = {} EQLV = {} LFCT = {}",
+            value(self.lclt),
+            value(self.cins),
+            value(self.eqlv),
+            value(self.lfc)
+        );
+        let _ = writeln!(text, "BRCH = {}", value(self.brch));
+        text.push('\n');
+
+        // Profiles, one column for each profile with values.
+        let mut columns: Vec<(&str, Vec<String>)> = vec![("PRES", values(&self.pressure))];
+        let mut add_column = |name, vals: Vec<String>| {
+            if !vals.is_empty() {
+                columns.push((name, vals));
+            }
+        };
+        add_column("TMPC", values(&self.temperature));
+        add_column("TMWC", values(&self.wet_bulb));
+        add_column("DWPC", values(&self.dew_point));
+        add_column("THTE", values(&self.theta_e));
+        add_column(
+            "DRCT",
+            self.wind
+                .iter()
+                .map(|wind| value(wind.map_t(|wind| wind.direction)))
+                .collect(),
+        );
+        add_column(
+            "SKNT",
+            self.wind
+                .iter()
+                .map(|wind| value(wind.map_t(|wind| wind.speed)))
+                .collect(),
+        );
+        add_column("OMEG", values(&self.omega));
+        add_column("CFRL", values(&self.cloud_fraction_percent()));
+        add_column("HGHT", values(&self.height));
+        add_column("MIXR", values(&self.mixing_ratio));
+
+        write_wrapped(&mut text, columns.iter().map(|(name, _)| *name));
+        for level in 0..self.pressure.len() {
+            write_wrapped(
+                &mut text,
+                columns.iter().map(|(_, vals)| vals[level].as_str()),
+            );
+        }
+
+        text
+    }
+}
+
+impl SurfaceData {
+    /// Write the surface data as bufkit text, the same as the surface section of a file with
+    /// only one valid time.
+    ///
+    /// This includes a header with every column that is parsed, and missing values are written
+    /// as `-9999.00`. Parsing the text gives back this surface data.
+    pub fn to_bufkit_string(&self) -> String {
+        let mut text = SURFACE_HEADER.to_owned();
+        self.write_bufkit_row(&mut text);
+        text
+    }
+
+    /// Write the values in the same order as the columns in `SURFACE_HEADER`.
+    pub(crate) fn write_bufkit_row(&self, text: &mut String) {
+        let flag =
+            |val: Option<bool>| value(Optioned::from(val.map(|val| if val { 1.0 } else { 0.0 })));
+        let cloud = |val: Optioned<f64>| value(val.map_t(|val| val * 100.0));
+
+        let wind = self.wind.map_t(WindUV::<MetersPSec>::from);
+        let storm = self.storm_motion;
+
+        let vals = [
+            self.station_num.to_string(),
+            bufkit_time(self.valid_time),
+            value(self.mslp),
+            value(self.station_pres),
+            value(self.skin_temp),
+            value(self.lyr_1_soil_temp),
+            value(self.snow_1hr),
+            value(self.p01),
+            value(self.c01),
+            value(self.lyr_2_soil_temp),
+            cloud(self.low_cloud),
+            cloud(self.mid_cloud),
+            cloud(self.hi_cloud),
+            value(self.snow_ratio),
+            value(wind.map_t(|wind| wind.u)),
+            value(wind.map_t(|wind| wind.v)),
+            value(self.temperature),
+            flag(self.snow_type),
+            flag(self.ice_pellets_type),
+            flag(self.fzra_type),
+            flag(self.rain_type),
+            value(storm.map_t(|storm| storm.u)),
+            value(storm.map_t(|storm| storm.v)),
+            value(self.srh),
+            value(self.wx_sym_cod),
+            value(self.cloud_base_pres),
+            value(self.visibility),
+            value(self.dewpoint),
+        ];
+
+        write_wrapped(text, vals.iter().map(String::as_str));
+    }
+}
+
+/// Format a valid time the way bufkit files do, e.g. 170401/0000.
+fn bufkit_time(valid_time: NaiveDateTime) -> String {
+    valid_time.format("%y%m%d/%H%M").to_string()
+}
+
+/// Format a value with two decimal places, or the missing value if it is missing.
+fn value<T>(val: Optioned<T>) -> String
+where
+    T: Quantity + Noned,
+{
+    format!("{:.2}", val.map_or(MISSING_F64, |val| val.unpack()))
+}
+
+/// Format every value in a profile.
+fn values<T>(vals: &[Optioned<T>]) -> Vec<String>
+where
+    T: Quantity + Noned,
+{
+    vals.iter().map(|&val| value(val)).collect()
+}
+
+/// Write the items separated by spaces, with a new line after every `VALUES_PER_LINE` items and
+/// at the end.
+fn write_wrapped<'a>(text: &mut String, items: impl Iterator<Item = &'a str>) {
+    for (count, item) in items.enumerate() {
+        if count > 0 {
+            let separator = if count % VALUES_PER_LINE == 0 {
+                '\n'
+            } else {
+                ' '
+            };
+            text.push(separator);
+        }
+        text.push_str(item);
+    }
+    text.push('\n');
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bufkit_data::surface_section::SurfaceSection;
+
+    const UPPER_AIR: &str = "STID = KMSO STNM = 727730 TIME = 170401/0000
+        SLAT = 46.87 SLON = -114.16 SELV = 1335.0
+        STIM = 0
+
+        SHOW = 8.40 LIFT = 7.97 SWET = 30.03 KINX = 15.11
+        LCLP = 773.81 PWAT = 9.40 TOTL = 39.07 CAPE = 0.78
+        LCLT = 272.32 CINS = -9.22 EQLV = 731.95 LFCT = -9999.00
+        BRCH = 0.04
+
+        PRES TMPC TMWC DWPC THTE DRCT SKNT OMEG
+        CFRL HGHT
+        867.80 8.24 4.63 0.76 306.90 315.00 0.27 0.00
+        0.00 1352.12
+        864.10 7.74 4.32 0.61 306.64 296.57 0.43 0.00
+        0.00 1387.38
+        860.40 7.34 4.08 -9999.00 306.55 315.00 0.27 0.00
+        0.00 1422.73
+        856.60 7.04 3.88 0.43 306.58 333.43 0.43 0.00
+        12.00 1459.16";
+
+    const SURFACE: &str = "STN YYMMDD/HHMM PMSL PRES SKTC STC1 SNFL WTNS
+        P01M C01M STC2 LCLD MCLD HCLD
+        SNRA UWND VWND R01M BFGR T2MS
+        Q2MS WXTS WXTP WXTZ WXTR USTM
+        VSTM HLCY SLLH WSYM CDBP VSBK
+        TD2M
+        727730 170401/0000 1017.40 869.60 10.64 284.90 -9999.00 74.00
+        -9999.00 -9999.00 277.20 0.00 0.00 1.00
+        0.00 0.40 -0.10 -9999.00 -9999.00 8.84
+        4.76 0.00 0.00 0.00 0.00 -7.10
+        -8.50 4.00 -9999.00 999.00 -9999.00 20.00
+        1.02
+        727730 170401/0100 1016.70 869.10 10.04 284.00 0.00 74.00
+        0.00 0.00 277.40 0.00 0.00 0.00
+        0.00 1.90 -0.40 0.00 0.07 8.54
+        4.89 0.00 1.00 0.00 1.00 -6.70
+        -8.10 4.10 -0.05 999.00 -9999.00 20.00
+        1.38";
+
+    #[test]
+    fn test_upper_air_round_trip() {
+        let snd = UpperAir::parse(UPPER_AIR).unwrap();
+        let round_trip = UpperAir::parse(&snd.to_bufkit_string()).unwrap();
+        // Missing values are NaN, so compare the Debug output instead of the values.
+        assert_eq!(format!("{:?}", round_trip), format!("{:?}", snd));
+    }
+
+    #[test]
+    fn test_surface_round_trip() {
+        let section = SurfaceSection::new(SURFACE).unwrap();
+        assert_eq!(section.into_iter().count(), 2);
+
+        for sd in &section {
+            let text = sd.to_bufkit_string();
+            let section = SurfaceSection::new(&text).unwrap();
+            let round_trip: Vec<SurfaceData> = section.into_iter().collect();
+            assert_eq!(round_trip.len(), 1);
+            assert_eq!(format!("{:?}", round_trip[0]), format!("{:?}", sd));
+        }
+    }
+
+    #[test]
+    fn test_missing_values() {
+        let sd = SurfaceData::default();
+        let text = sd.to_bufkit_string();
+        assert!(text.contains(" -9999.00 "));
+
+        let section = SurfaceSection::new(&text).unwrap();
+        let round_trip: Vec<SurfaceData> = section.into_iter().collect();
+        assert!(round_trip.iter().all(|sd| sd.mslp.is_none()));
+    }
+
+    #[test]
+    fn test_write_wrapped() {
+        let mut text = String::new();
+        write_wrapped(&mut text, "1 2 3 4 5 6 7 8 9".split(' '));
+        assert_eq!(text, "1 2 3 4 5 6 7 8\n9\n");
+    }
+}
@@ -1,28 +1,239 @@
 //! Errors specific to the sounding-bufkit crate.
+use crate::bufkit_data::ProfileField;
+use chrono::NaiveDateTime;
 use std::error::Error;
 use std::fmt::{Display, Formatter, Result};
+use std::num::{ParseFloatError, ParseIntError};
 
-/// Basic error originating in this crate with a backtrace.
-#[derive(Debug)]
-pub struct BufkitFileError {}
+/// Error originating in this crate while parsing a bufkit file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BufkitFileError {
+    /// A required column was not in the header of a section.
+    MissingColumn(String),
+    /// A column in the header of a profile is not one this crate knows how to parse.
+    UnknownColumn(String),
+    /// A column shows up more than once in the header of a profile.
+    DuplicateColumn(String),
+    /// Could not find the surface section header, which marks the break between the upper air
+    /// and surface sections.
+    MissingBreakPoint,
+    /// A section of a sounding, e.g. the station info or indexes, could not be found.
+    MissingSection(String),
+    /// A key in a "KEY = VALUE" pair could not be found, or it had no value.
+    MissingValue(String),
+    /// A valid time could not be parsed.
+    BadDateTime(String),
+    /// A floating point number could not be parsed.
+    ParseFloat(String),
+    /// An integer could not be parsed.
+    ParseInt(String),
+    /// A row of data had fewer values than there are columns.
+    ShortRow {
+        /// The number of columns.
+        expected: usize,
+        /// The number of values found.
+        found: usize,
+    },
+    /// A record in the surface section was shifted into its neighbor by a missing or extra value.
+    MisalignedRecord,
+    /// A profile has no values, or no pressure values.
+    EmptyProfile,
+    /// A profile has a different number of levels than the pressure profile.
+    ProfileLengthMismatch {
+        /// The number of pressure levels.
+        expected: usize,
+        /// The number of levels in the mismatched profile.
+        found: usize,
+    },
+    /// A required profile is empty.
+    MissingProfile(ProfileField),
+    /// A required profile is missing the value at a level.
+    ProfileGap {
+        /// The profile with the missing value.
+        field: ProfileField,
+        /// The index of the first level with a missing value.
+        level: usize,
+    },
+    /// The pressure in a profile does not decrease with height.
+    PressureNotDecreasing {
+        /// The index of the first level with a pressure that is not less than the level below.
+        level: usize,
+    },
+    /// A valid time is not after the one before it, so the times are duplicated or out of order.
+    TimeNotIncreasing(NaiveDateTime),
+    /// The upper air and surface sections have no valid times in common, so there are no
+    /// soundings.
+    DisjointTimes {
+        /// The first few valid times in the upper air section.
+        upper_air: Vec<NaiveDateTime>,
+        /// The first few valid times in the surface section.
+        surface: Vec<NaiveDateTime>,
+    },
+    /// Data that should be for one station has more than one station number.
+    StationMismatch {
+        /// The station number of the first data.
+        expected: i32,
+        /// The station number that doesn't match it.
+        found: i32,
+    },
+    /// Data read back from a cache was not created by a compatible version of this crate.
+    InvalidCache,
+    /// Another error, along with the line in the file where it happened.
+    AtLine {
+        /// The line number, starting at 1.
+        line: usize,
+        /// The error at that line.
+        error: Box<BufkitFileError>,
+    },
+}
 
 impl BufkitFileError {
-    /// Createa new BufkitFileError.
-    pub fn new() -> BufkitFileError {
-        BufkitFileError {}
+    /// Attach the line number where this error happened.
+    ///
+    /// If the error already has a line number, it is relative to the start of a smaller piece of
+    /// text that starts at `line`, so the line numbers are combined.
+    pub(crate) fn located(self, line: usize) -> BufkitFileError {
+        match self {
+            BufkitFileError::AtLine {
+                line: inner_line,
+                error,
+            } => BufkitFileError::AtLine {
+                line: line + inner_line - 1,
+                error,
+            },
+            error => BufkitFileError::AtLine {
+                line,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// Get the line number where this error happened, if it is known.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            BufkitFileError::AtLine { line, .. } => Some(*line),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseFloatError> for BufkitFileError {
+    fn from(err: ParseFloatError) -> Self {
+        BufkitFileError::ParseFloat(err.to_string())
+    }
+}
+
+impl From<ParseIntError> for BufkitFileError {
+    fn from(err: ParseIntError) -> Self {
+        BufkitFileError::ParseInt(err.to_string())
     }
 }
 
 impl Display for BufkitFileError {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        writeln!(f, "Error parsing bufkit file.")
+        use BufkitFileError::*;
+
+        match self {
+            MissingColumn(col) => write!(f, "missing required column: {}", col),
+            UnknownColumn(col) => write!(f, "unknown column in profile: {}", col),
+            DuplicateColumn(col) => write!(f, "duplicate column: {}", col),
+            MissingBreakPoint => write!(f, "could not find the start of the surface section"),
+            MissingSection(section) => write!(f, "missing {} section", section),
+            MissingValue(key) => write!(f, "missing value for {}", key),
+            BadDateTime(text) => write!(f, "invalid date and time: {}", text),
+            ParseFloat(text) => write!(f, "invalid number: {}", text),
+            ParseInt(text) => write!(f, "invalid integer: {}", text),
+            ShortRow { expected, found } => write!(
+                f,
+                "short row, expected {} values but found {}",
+                expected, found
+            ),
+            MisalignedRecord => write!(f, "surface record with a missing or extra value"),
+            EmptyProfile => write!(f, "empty profile"),
+            ProfileLengthMismatch { expected, found } => write!(
+                f,
+                "profile has {} levels, but there are {} pressure levels",
+                found, expected
+            ),
+            MissingProfile(field) => write!(f, "missing {} profile", field),
+            ProfileGap { field, level } => {
+                write!(
+                    f,
+                    "{} profile is missing the value at level {}",
+                    field, level
+                )
+            }
+            PressureNotDecreasing { level } => {
+                write!(f, "pressure does not decrease at level {}", level)
+            }
+            TimeNotIncreasing(time) => write!(
+                f,
+                "valid time {} is not after the one before it",
+                time.format("%Y-%m-%d %H:%MZ")
+            ),
+            DisjointTimes { upper_air, surface } => {
+                let list = |times: &[NaiveDateTime]| {
+                    times
+                        .iter()
+                        .map(|time| time.format("%Y-%m-%d %H:%MZ").to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                write!(
+                    f,
+                    "upper air and surface sections have no valid times in common, upper air \
+                     times start with {} and surface times start with {}",
+                    list(upper_air),
+                    list(surface)
+                )
+            }
+            StationMismatch { expected, found } => write!(
+                f,
+                "station number {} does not match station number {}",
+                found, expected
+            ),
+            AtLine { line, error } => write!(f, "error at line {}: {}", line, error),
+            InvalidCache => write!(
+                f,
+                "not a cache from a compatible version of sounding-bufkit"
+            ),
+        }
     }
 }
 
 impl Error for BufkitFileError {}
 
-impl Default for BufkitFileError {
-    fn default() -> Self {
-        BufkitFileError::new()
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            BufkitFileError::MissingColumn("STN".to_owned()).to_string(),
+            "missing required column: STN"
+        );
+        assert_eq!(
+            BufkitFileError::ShortRow {
+                expected: 23,
+                found: 22
+            }
+            .to_string(),
+            "short row, expected 23 values but found 22"
+        );
+        assert_eq!(
+            BufkitFileError::BadDateTime("170401-0000".to_owned()).to_string(),
+            "invalid date and time: 170401-0000"
+        );
+
+        let err: BufkitFileError = "12x".parse::<i32>().unwrap_err().into();
+        assert!(matches!(err, BufkitFileError::ParseInt(_)));
+        let err: BufkitFileError = "1.x".parse::<f64>().unwrap_err().into();
+        assert!(matches!(err, BufkitFileError::ParseFloat(_)));
+
+        let err = BufkitFileError::EmptyProfile.located(3).located(10);
+        assert_eq!(err.line(), Some(12));
+        assert_eq!(err.to_string(), "error at line 12: empty profile");
     }
 }
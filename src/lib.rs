@@ -8,13 +8,22 @@
 // API
 //
 
-pub use crate::bufkit_data::{BufkitData, BufkitFile, SoundingIterator};
+pub use crate::analysis::{get_index, keys, AnalysisKey, ProviderAnalysis};
+pub use crate::bufkit_data::{
+    load_directory, merge_soundings, BufkitData, BufkitFile, Level, OwnedSoundings, ProfileField,
+    SfcColName, SfcColumns, SoundingIterator, SurfaceData, SurfaceIterator, SurfaceSection,
+    UpperAir, UpperAirBuilder, UpperAirIterator, Warning,
+};
 pub use crate::error::*;
+pub use crate::model::Model;
+pub use crate::parse_util::ParseOptions;
 
 //
 // Internal use only
 //
 
+mod analysis;
 mod bufkit_data;
 mod error;
+mod model;
 mod parse_util;
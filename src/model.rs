@@ -0,0 +1,65 @@
+//! The model that produced a bufkit file.
+
+/// Models that produce bufkit files, guessed from the file name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Model {
+    /// Global Forecast System, usually 3-hourly output.
+    Gfs,
+    /// North American Mesoscale model, hourly output.
+    Nam,
+    /// The high resolution nest of the NAM, hourly output.
+    NamNest,
+    /// High Resolution Rapid Refresh, hourly output.
+    Hrrr,
+    /// The model could not be determined from the file name.
+    Unknown,
+}
+
+impl Model {
+    /// Guess the model from a bufkit file name, e.g. "2017040100Z_gfs3_kmso.buf".
+    ///
+    /// The file name is split into words at any character that is not a letter or number, and
+    /// the first word that names a model decides it.
+    pub fn from_file_name(file_name: &str) -> Model {
+        file_name
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .map(|word| word.to_ascii_lowercase())
+            .find_map(|word| match word.as_str() {
+                "namm" | "nam4km" | "namnest" => Some(Model::NamNest),
+                "nam" => Some(Model::Nam),
+                "hrrr" => Some(Model::Hrrr),
+                w if w.starts_with("gfs") => Some(Model::Gfs),
+                _ => None,
+            })
+            .unwrap_or(Model::Unknown)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_file_name() {
+        assert_eq!(
+            Model::from_file_name("2017040100Z_gfs_kmso.buf"),
+            Model::Gfs
+        );
+        assert_eq!(
+            Model::from_file_name("2017040112Z_gfs3_kmso.buf"),
+            Model::Gfs
+        );
+        assert_eq!(
+            Model::from_file_name("2017040100Z_nam_kmso.buf"),
+            Model::Nam
+        );
+        assert_eq!(
+            Model::from_file_name("2017040106Z_namm_kmso.buf"),
+            Model::NamNest
+        );
+        assert_eq!(Model::from_file_name("nam4km_kmso.buf"), Model::NamNest);
+        assert_eq!(Model::from_file_name("HRRR_KMSO.BUF"), Model::Hrrr);
+        assert_eq!(Model::from_file_name("kmso.buf"), Model::Unknown);
+        assert_eq!(Model::from_file_name("Unknown File"), Model::Unknown);
+    }
+}
@@ -1,9 +1,7 @@
 //! Utilites for parsing a sounding.
-use std::error::Error;
-
 use crate::error::*;
 use chrono::{NaiveDate, NaiveDateTime};
-use optional::{none, some, Optioned};
+use optional::{none, some, Noned, Optioned};
 
 // Missing or no data values used in Bufkit files
 pub(crate) const MISSING_I32: i32 = -9999;
@@ -18,6 +16,90 @@ pub(crate) fn check_missing(val: f64) -> Optioned<f64> {
     }
 }
 
+/// Options that control how the values in a bufkit file are parsed.
+///
+/// The default options parse standard bufkit files, where `-9999.00` marks a missing value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseOptions {
+    missing_values: Vec<f64>,
+}
+
+impl ParseOptions {
+    /// Treat `val` as a missing value too, for files that use something like `-999` or `9999`
+    /// instead of, or as well as, `-9999.00`.
+    ///
+    /// `-9999.00` is always treated as missing.
+    pub fn with_missing_value(mut self, val: f64) -> Self {
+        self.missing_values.push(val);
+        self
+    }
+
+    /// Get the values treated as missing, other than `-9999.00`.
+    pub fn missing_values(&self) -> &[f64] {
+        &self.missing_values
+    }
+
+    /// Same as the `check_missing` function, but also check the extra missing values.
+    pub(crate) fn check_missing(&self, val: f64) -> Optioned<f64> {
+        if self.missing_values.contains(&val) {
+            none()
+        } else {
+            check_missing(val)
+        }
+    }
+}
+
+#[test]
+fn test_parse_options_check_missing() {
+    let default = ParseOptions::default();
+    assert!(default.check_missing(MISSING_F64).is_none());
+    assert_eq!(default.check_missing(-999.0), some(-999.0));
+
+    let options = ParseOptions::default().with_missing_value(-999.0);
+    assert_eq!(options.missing_values(), &[-999.0]);
+    assert!(options.check_missing(MISSING_F64).is_none());
+    assert!(options.check_missing(-999.0).is_none());
+    assert_eq!(options.check_missing(999.0), some(999.0));
+}
+
+/// Get the line number, starting at 1, in `text` where `sub` starts.
+///
+/// `sub` must be a sub-slice of `text`.
+pub(crate) fn line_of(text: &str, sub: &str) -> usize {
+    let offset = sub.as_ptr() as usize - text.as_ptr() as usize;
+    text[..offset].matches('\n').count() + 1
+}
+
+/// Get the byte range in `text` covered by `sub`.
+///
+/// `sub` must be a sub-slice of `text`.
+pub(crate) fn range_of(text: &str, sub: &str) -> std::ops::Range<usize> {
+    let offset = sub.as_ptr() as usize - text.as_ptr() as usize;
+    offset..(offset + sub.len())
+}
+
+#[test]
+fn test_line_of() {
+    let text = "STID = KMSO\r\nSTNM = 727730\n\nTIME = 170401/0000";
+    assert_eq!(line_of(text, text), 1);
+    assert_eq!(line_of(text, &text[text.find("STNM").unwrap()..]), 2);
+    assert_eq!(line_of(text, &text[text.find("TIME").unwrap()..]), 4);
+}
+
+/// Check if every value in a profile is missing.
+///
+/// An empty profile is also considered all missing.
+pub(crate) fn is_all_missing<T: Noned + Copy>(vals: &[Optioned<T>]) -> bool {
+    vals.iter().all(|val| val.is_none())
+}
+
+#[test]
+fn test_is_all_missing() {
+    assert!(is_all_missing::<f64>(&[]));
+    assert!(is_all_missing::<f64>(&[none(), none(), none()]));
+    assert!(!is_all_missing(&[none(), some(0.0), none()]));
+}
+
 pub(crate) fn check_missing_i32(val: i32) -> Option<i32> {
     if val == MISSING_I32 {
         None
@@ -33,9 +115,9 @@ pub(crate) fn check_missing_i32(val: i32) -> Option<i32> {
 /// character in the sub-string you want to keep, return a tuple with the first value as the
 /// sub-string you were looking for and the second value the remainder of `src` after this
 /// sub-string has been parsed out.
-pub fn parse_kv<'a, 'b, FS, FE>(
+pub fn parse_kv<'a, FS, FE>(
     src: &'a str,
-    key: &'b str,
+    key: &str,
     start_val: FS,
     end_val: FE,
 ) -> Result<(&'a str, &'a str), BufkitFileError>
@@ -43,14 +125,16 @@ where
     FS: Fn(char) -> bool,
     FE: Fn(char) -> bool,
 {
-    let mut idx = src.find(key).ok_or_else(BufkitFileError::new)?;
+    let missing = || BufkitFileError::MissingValue(key.to_owned());
+
+    let mut idx = src.find(key).ok_or_else(missing)?;
     let head = &src[idx..];
     let mut head = head.trim_start_matches(key);
-    idx = head.find(start_val).ok_or_else(BufkitFileError::new)?;
+    idx = head.find(start_val).ok_or_else(missing)?;
     head = &head[idx..];
     // When finding the end of the value, you may go all the way to the end of the slice.
     // If so, find returns None, just convert that into the end of the slice.
-    let tail_idx = head.find(end_val).or_else(|| Some(head.len())).unwrap();
+    let tail_idx = head.find(end_val).unwrap_or(head.len());
     Ok((head[..tail_idx].trim(), &head[tail_idx..]))
 }
 
@@ -65,12 +149,12 @@ fn test_parse_kv() {
     if let Ok((value_to_parse, head)) =
         parse_kv(test_data,
                  "STID",
-                 |c| char::is_alphanumeric(c),
+                 char::is_alphanumeric,
                  |c| !char::is_alphanumeric(c)) {
         assert_eq!(value_to_parse, "STNM");
         assert_eq!(head, " = 727730 TIME = 170401/0000 SLAT = 46.92 SLON = -114.08 SELV = 972.0 STIM = 0");
     } else {
-        assert!(false, "There was an error parsing.");
+        panic!("There was an error parsing.");
     }
 
     if let Ok((value_to_parse, head)) =
@@ -81,7 +165,7 @@ fn test_parse_kv() {
         assert_eq!(value_to_parse, "727730");
         assert_eq!(head, " TIME = 170401/0000 SLAT = 46.92 SLON = -114.08 SELV = 972.0 STIM = 0");
     } else {
-        assert!(false, "There was an error parsing.");
+        panic!("There was an error parsing.");
     }
 
     if let Ok((val_to_parse, head)) =
@@ -92,7 +176,7 @@ fn test_parse_kv() {
         assert_eq!(val_to_parse, "170401/0000");
         assert_eq!(head, " SLAT = 46.92 SLON = -114.08 SELV = 972.0 STIM = 0");
     } else {
-        assert!(false, "There was an error parsing.");
+        panic!("There was an error parsing.");
     }
 
     if let Ok((val_to_parse, head)) =
@@ -103,15 +187,16 @@ fn test_parse_kv() {
         assert_eq!(val_to_parse, "0");
         assert_eq!(head, "");
     } else {
-        assert!(false, "There was an error parsing the very last element.");
+        panic!("There was an error parsing the very last element.");
     }
 }
 
-/// Parse an f64 value.
-pub fn parse_f64<'a, 'b>(
+/// Parse an f64 value, checking it against the missing values in `options`.
+pub fn parse_f64<'a>(
     src: &'a str,
-    key: &'b str,
-) -> Result<(Optioned<f64>, &'a str), Box<dyn Error>> {
+    key: &str,
+    options: &ParseOptions,
+) -> Result<(Optioned<f64>, &'a str), BufkitFileError> {
     use std::str::FromStr;
 
     let (val_to_parse, head) = parse_kv(
@@ -120,8 +205,9 @@ pub fn parse_f64<'a, 'b>(
         |c| char::is_digit(c, 10) || c == '-',
         |c| !(char::is_digit(c, 10) || c == '.' || c == '-'),
     )?;
-    let val = check_missing(f64::from_str(val_to_parse)?);
-    Ok((val, head))
+    let val = f64::from_str(val_to_parse)
+        .map_err(|_| BufkitFileError::ParseFloat(val_to_parse.to_owned()))?;
+    Ok((options.check_missing(val), head))
 }
 
 #[test]
@@ -132,23 +218,23 @@ fn test_parse_f64() {
          SLAT = 46.92 SLON = -114.08 SELV = 972.0 \
          STIM = 0";
 
-    if let Ok((lat, head)) = parse_f64(test_data, "SLAT") {
+    if let Ok((lat, head)) = parse_f64(test_data, "SLAT", &ParseOptions::default()) {
         assert_eq!(lat, some(46.92));
         assert_eq!(head, " SLON = -114.08 SELV = 972.0 STIM = 0");
     } else {
-        assert!(false, "There was an error parsing.");
+        panic!("There was an error parsing.");
     }
 
-    if let Ok((lon, head)) = parse_f64(test_data, "SLON") {
+    if let Ok((lon, head)) = parse_f64(test_data, "SLON", &ParseOptions::default()) {
         assert_eq!(lon, some(-114.08));
         assert_eq!(head, " SELV = 972.0 STIM = 0");
     } else {
-        assert!(false, "There was an error parsing.");
+        panic!("There was an error parsing.");
     }
 }
 
 /// Parse an i32 value.
-pub fn parse_i32<'a, 'b>(src: &'a str, key: &'b str) -> Result<(i32, &'a str), Box<dyn Error>> {
+pub fn parse_i32<'a>(src: &'a str, key: &str) -> Result<(i32, &'a str), BufkitFileError> {
     use std::str::FromStr;
 
     let (val_to_parse, head) = parse_kv(
@@ -157,7 +243,8 @@ pub fn parse_i32<'a, 'b>(src: &'a str, key: &'b str) -> Result<(i32, &'a str), B
         |c| char::is_digit(c, 10),
         |c| !char::is_digit(c, 10),
     )?;
-    let val = i32::from_str(val_to_parse)?;
+    let val = i32::from_str(val_to_parse)
+        .map_err(|_| BufkitFileError::ParseInt(val_to_parse.to_owned()))?;
     Ok((val, head))
 }
 
@@ -173,43 +260,65 @@ fn test_parse_i32() {
         assert_eq!(stnm, 727730);
         assert_eq!(head, " TIME = 170401/0000 SLAT = 46.92 SLON = -114.08 SELV = 972.0 STIM = 0");
     } else {
-        assert!(false, "There was an error parsing.");
+        panic!("There was an error parsing.");
     }
 
     if let Ok((ymd, head)) = parse_i32(test_data, "TIME") {
         assert_eq!(ymd, 170401);
         assert_eq!(head, "/0000 SLAT = 46.92 SLON = -114.08 SELV = 972.0 STIM = 0");
     } else {
-        assert!(false, "There was an error parsing.");
+        panic!("There was an error parsing.");
     }
+
+    assert_eq!(
+        parse_i32("STNM = 99999999999", "STNM").unwrap_err(),
+        BufkitFileError::ParseInt("99999999999".to_owned())
+    );
 }
 
 /// Parse a string of the form "YYmmdd/hhMM" to a `NaiveDateTime`.
-pub fn parse_naive_date_time(src: &str) -> Result<NaiveDateTime, Box<dyn Error>> {
+pub fn parse_naive_date_time(src: &str) -> Result<NaiveDateTime, BufkitFileError> {
     use std::str::FromStr;
 
     let val_to_parse = src.trim();
+    let bad = || BufkitFileError::BadDateTime(val_to_parse.to_owned());
+    if val_to_parse.len() != 11 || val_to_parse.as_bytes()[6] != b'/' {
+        return Err(bad());
+    }
 
-    let year = i32::from_str(&val_to_parse[..2])? + 2000;
-    let month = u32::from_str(&val_to_parse[2..4])?;
-    let day = u32::from_str(&val_to_parse[4..6])?;
-    let hour = u32::from_str(&val_to_parse[7..9])?;
-    let minute = u32::from_str(&val_to_parse[9..11])?;
-    Ok(NaiveDate::from_ymd(year, month, day).and_hms(hour, minute, 0))
+    let year = i32::from_str(&val_to_parse[..2]).map_err(|_| bad())? + 2000;
+    let month = u32::from_str(&val_to_parse[2..4]).map_err(|_| bad())?;
+    let day = u32::from_str(&val_to_parse[4..6]).map_err(|_| bad())?;
+    let hour = u32::from_str(&val_to_parse[7..9]).map_err(|_| bad())?;
+    let minute = u32::from_str(&val_to_parse[9..11]).map_err(|_| bad())?;
+    NaiveDate::from_ymd_opt(year, month, day)
+        .and_then(|date| date.and_hms_opt(hour, minute, 0))
+        .ok_or_else(bad)
 }
 
 #[test]
 fn test_parse_naive_date_time() {
     let test_data = " 170401/0000 ";
 
+    assert!(parse_naive_date_time("727730").is_err());
+    assert!(parse_naive_date_time("1.0").is_err());
+    assert!(parse_naive_date_time("170401/00").is_err());
+
     let test_value = parse_naive_date_time(test_data).unwrap();
-    assert_eq!(test_value, NaiveDate::from_ymd(2017, 4, 1).and_hms(0, 0, 0));
+    assert_eq!(
+        test_value,
+        NaiveDate::from_ymd_opt(2017, 4, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    );
 }
 
 /// Find a blank line, or a line without any ASCII numbers or letters.
 ///
 /// Return `None` if one cannot be found, otherwise return the byte location of the character just
-/// after the second newline.
+/// after the second newline. A carriage return is just white space on the blank line, so this
+/// works the same with CRLF line endings.
 pub fn find_blank_line(src: &str) -> Option<usize> {
     let mut first_newline = false;
 
@@ -259,11 +368,22 @@ fn test_find_blank_line() {
 
     assert!(the_rest.trim().starts_with("PRES TMPC TMWC"));
     assert!(find_blank_line(the_rest).is_none());
+
+    // Same thing with CRLF line endings.
+    let crlf = test_string.replace('\n', "\r\n");
+    let (crlf_station_info, crlf_rest) = crlf.split_at(find_blank_line(&crlf).unwrap());
+    let (crlf_indexes, crlf_rest) = crlf_rest.split_at(find_blank_line(crlf_rest).unwrap());
+    assert_eq!(crlf_station_info.replace('\r', ""), station_info);
+    assert_eq!(crlf_indexes.replace('\r', ""), indexes);
+    assert_eq!(crlf_rest.replace('\r', ""), the_rest);
+    assert!(find_blank_line(crlf_rest).is_none());
 }
 
 /// In a list of white space delimited floating point values, find a string with `n` values.
+///
+/// Carriage returns are white space, so CRLF line endings split values like LF line endings.
 pub fn find_next_n_tokens(src: &str, n: usize) -> Result<Option<usize>, BufkitFileError> {
-    if src.trim().is_empty() {
+    if n == 0 || src.trim().is_empty() {
         return Ok(None);
     }
 
@@ -295,7 +415,10 @@ pub fn find_next_n_tokens(src: &str, n: usize) -> Result<Option<usize>, BufkitFi
 
     // Invalid number of tokens
     if token_count > 0 {
-        return Err(BufkitFileError::new());
+        return Err(BufkitFileError::ShortRow {
+            expected: n,
+            found: token_count + usize::from(!in_white_space),
+        });
     }
     // Out of tokens
     Ok(None)
@@ -367,4 +490,97 @@ fn test_find_next_n_tokens() {
     );
 
     assert_eq!(find_next_n_tokens(remaining, 33).unwrap(), None);
+
+    // CRLF line endings split into the same values.
+    let crlf = test_data.replace('\n', "\r\n");
+    let mut lf_remaining = test_data;
+    let mut crlf_remaining = crlf.as_str();
+    while let Some(brk) = find_next_n_tokens(lf_remaining, 33).unwrap() {
+        let crlf_brk = find_next_n_tokens(crlf_remaining, 33).unwrap().unwrap();
+        let (lf_chunk, lf_rest) = lf_remaining.split_at(brk);
+        let (crlf_chunk, crlf_rest) = crlf_remaining.split_at(crlf_brk);
+        assert!(lf_chunk
+            .split_whitespace()
+            .eq(crlf_chunk.split_whitespace()));
+        assert!(!crlf_chunk.ends_with('\r'));
+        lf_remaining = lf_rest;
+        crlf_remaining = crlf_rest;
+    }
+    assert_eq!(find_next_n_tokens(crlf_remaining, 33).unwrap(), None);
+}
+
+/// In a list of white space delimited floating point values, find the start of the last `n`
+/// values.
+pub fn find_last_n_tokens(src: &str, n: usize) -> Result<Option<usize>, BufkitFileError> {
+    if n == 0 || src.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let mut token_count = 0;
+    let mut in_token = false;
+
+    for (i, c) in src.char_indices().rev() {
+        if c.is_whitespace() {
+            if in_token {
+                // Just passed out of a token, increase token count
+                token_count += 1;
+                in_token = false;
+
+                if token_count == n {
+                    return Ok(Some(i + c.len_utf8()));
+                }
+            }
+        } else {
+            in_token = true;
+        }
+    }
+
+    // Special case for the start of the string
+    if in_token && token_count == n - 1 {
+        return Ok(Some(0));
+    }
+
+    // Invalid number of tokens
+    Err(BufkitFileError::ShortRow {
+        expected: n,
+        found: token_count + usize::from(in_token),
+    })
+}
+
+#[test]
+fn test_find_last_n_tokens() {
+    let test_data = "
+        727730 170401/0700 1021.50 869.80 0.14 275.50
+        727730 170401/0800 1022.00 869.70 -0.36 274.90
+        727730 170401/0900 1022.80 869.80 -0.46 274.80
+        ";
+
+    let brk = find_last_n_tokens(test_data, 6).unwrap().unwrap();
+    let (remaining, substr) = test_data.split_at(brk);
+    assert_eq!(
+        substr.trim(),
+        "727730 170401/0900 1022.80 869.80 -0.46 274.80"
+    );
+
+    let brk = find_last_n_tokens(remaining, 6).unwrap().unwrap();
+    let (remaining, substr) = remaining.split_at(brk);
+    assert_eq!(
+        substr.trim(),
+        "727730 170401/0800 1022.00 869.70 -0.36 274.90"
+    );
+
+    let brk = find_last_n_tokens(remaining, 6).unwrap().unwrap();
+    let (remaining, substr) = remaining.split_at(brk);
+    assert_eq!(
+        substr.trim(),
+        "727730 170401/0700 1021.50 869.80 0.14 275.50"
+    );
+
+    assert_eq!(find_last_n_tokens(remaining, 6).unwrap(), None);
+
+    // Not enough tokens left
+    assert!(find_last_n_tokens("1.0 2.0 3.0", 6).is_err());
+
+    assert_eq!(find_last_n_tokens("1.0 2.0 3.0", 0).unwrap(), None);
+    assert_eq!(find_next_n_tokens("1.0 2.0 3.0", 0).unwrap(), None);
 }
@@ -1,8 +1,12 @@
-use sounding_bufkit::BufkitFile;
+use sounding_bufkit::{BufkitFile, Model};
 use std::path::Path;
 
 const EXAMPLE_DIR: &str = "example_data";
 
+fn load(name: &str) -> BufkitFile {
+    BufkitFile::load(&Path::new(EXAMPLE_DIR).join(name)).expect("Error loading data.")
+}
+
 #[test]
 fn test_bufkit_file() {
     let example_dir = Path::new(EXAMPLE_DIR);
@@ -24,12 +28,16 @@ fn test_bufkit_file() {
             println!("Skipping");
             continue;
         }
-        if file.to_str().unwrap().contains("nam") {
-            println!("Got a nam: {}", data.into_iter().count());
-            assert_eq!(85, data.into_iter().count());
-        } else if file.to_str().unwrap().contains("gfs") {
-            println!("Got a gfs: {}", data.into_iter().count());
-            assert_eq!(61, data.into_iter().count());
+        match example_file.model() {
+            Model::Nam | Model::NamNest => {
+                println!("Got a nam: {}", data.into_iter().count());
+                assert_eq!(85, data.into_iter().count());
+            }
+            Model::Gfs => {
+                println!("Got a gfs: {}", data.into_iter().count());
+                assert_eq!(61, data.into_iter().count());
+            }
+            model => panic!("Unexpected model: {:?}", model),
         }
     }
 }
@@ -79,3 +87,990 @@ fn validate_dir(dir: &Path) {
         validate_dir(&dir);
     }
 }
+
+#[cfg(feature = "cache")]
+#[test]
+fn test_cache_round_trip() {
+    use sounding_bufkit::BufkitData;
+
+    let example_file = load("2017040100Z_nam_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    let mut cache: Vec<u8> = vec![];
+    data.write_cache(&mut cache).expect("Error writing cache");
+
+    let from_cache = BufkitData::read_cache(cache.as_slice()).expect("Error reading cache");
+    assert_eq!(from_cache.len(), 85);
+
+    for ((snd, anal), (c_snd, c_anal)) in data.into_iter().zip(from_cache) {
+        assert_eq!(snd.valid_time(), c_snd.valid_time());
+        assert_eq!(snd.pressure_profile(), c_snd.pressure_profile());
+        assert_eq!(snd.temperature_profile(), c_snd.temperature_profile());
+        assert_eq!(snd.wind_profile(), c_snd.wind_profile());
+        assert_eq!(snd.source_description(), c_snd.source_description());
+        assert_eq!(anal, c_anal);
+    }
+}
+
+#[test]
+fn test_len() {
+    let example_file = load("2017040106Z_gfs_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    assert_eq!(data.len(), 61);
+    assert_eq!(data.len(), data.into_iter().count());
+    assert!(!data.is_empty());
+}
+
+#[test]
+fn test_reversed_iteration() {
+    let example_file = load("2017040106Z_gfs_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    let mut forward: Vec<_> = data
+        .into_iter()
+        .map(|(snd, _)| snd.valid_time().unwrap())
+        .collect();
+    let backward: Vec<_> = data
+        .into_iter()
+        .rev()
+        .map(|(snd, _)| snd.valid_time().unwrap())
+        .collect();
+
+    assert_eq!(backward.len(), 61);
+    forward.reverse();
+    assert_eq!(forward, backward);
+}
+
+#[test]
+fn test_sounding_at() {
+    use chrono::NaiveDate;
+
+    let example_file = load("2017040106Z_gfs_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    let time = NaiveDate::from_ymd_opt(2017, 4, 2)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap();
+
+    let (snd, anal) = data.sounding_at(time).expect("Missing sounding");
+    let (expected_snd, expected_anal) = data
+        .into_iter()
+        .find(|(snd, _)| snd.valid_time() == Some(time))
+        .unwrap();
+
+    assert_eq!(snd.valid_time(), Some(time));
+    assert_eq!(snd.pressure_profile(), expected_snd.pressure_profile());
+    assert_eq!(
+        snd.temperature_profile(),
+        expected_snd.temperature_profile()
+    );
+    assert_eq!(anal, expected_anal);
+}
+
+#[test]
+fn test_sounding_at_missing_time() {
+    use chrono::NaiveDate;
+
+    let example_file = load("2017040106Z_gfs_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    // The GFS is output every 3 hours, and this file doesn't go back to 2016.
+    let off_hour = NaiveDate::from_ymd_opt(2017, 4, 2)
+        .unwrap()
+        .and_hms_opt(13, 0, 0)
+        .unwrap();
+    let too_early = NaiveDate::from_ymd_opt(2016, 4, 1)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap();
+
+    assert!(data.sounding_at(off_hour).is_none());
+    assert!(data.sounding_at(too_early).is_none());
+}
+
+#[test]
+fn test_valid_times() {
+    let example_file = load("2017040100Z_nam_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    let times = data.valid_times();
+    assert_eq!(times.len(), 85);
+    assert!(times.windows(2).all(|pair| pair[0] < pair[1]));
+
+    let expected: Vec<_> = data
+        .into_iter()
+        .map(|(snd, _)| snd.valid_time().unwrap())
+        .collect();
+    assert_eq!(times, expected);
+}
+
+#[test]
+fn test_soundings_by_day() {
+    use chrono::NaiveDate;
+
+    let example_file = load("2017040100Z_nam_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    let days = data.soundings_by_day();
+
+    // 84 hourly forecasts starting at 00Z April 1st run through 12Z April 4th.
+    let dates: Vec<_> = days.keys().cloned().collect();
+    assert_eq!(
+        dates,
+        (1..=4)
+            .map(|d| NaiveDate::from_ymd_opt(2017, 4, d).unwrap())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(days[&dates[0]].len(), 24);
+    assert_eq!(days.values().map(|snds| snds.len()).sum::<usize>(), 85);
+
+    for (date, snds) in &days {
+        assert!(snds
+            .iter()
+            .all(|(snd, _)| snd.valid_time().unwrap().date() == *date));
+    }
+}
+
+#[test]
+fn test_daily_precip_totals() {
+    use chrono::NaiveDate;
+    use metfor::Quantity;
+
+    let example_file = load("2017040100Z_nam_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    let april = |d| NaiveDate::from_ymd_opt(2017, 4, d).unwrap();
+
+    let totals = data.daily_precip_totals(true);
+    assert_eq!(totals.len(), 4);
+    assert!((totals[&april(1)].unpack() - 0.0).abs() < 1.0e-9);
+    assert!((totals[&april(2)].unpack() - 0.03).abs() < 1.0e-9);
+    assert!((totals[&april(3)].unpack() - 0.2).abs() < 1.0e-9);
+    assert!((totals[&april(4)].unpack() - 0.0).abs() < 1.0e-9);
+
+    // The analysis hour has no 1-hour precipitation, so the first day is incomplete.
+    let totals = data.daily_precip_totals(false);
+    assert_eq!(totals.len(), 3);
+    assert!(!totals.contains_key(&april(1)));
+    assert!((totals[&april(3)].unpack() - 0.2).abs() < 1.0e-9);
+}
+
+#[test]
+fn test_init_time() {
+    use chrono::NaiveDate;
+
+    let example_file = load("2017040112Z_nam_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    assert_eq!(
+        data.init_time(),
+        Some(
+            NaiveDate::from_ymd_opt(2017, 4, 1)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap()
+        )
+    );
+}
+
+#[test]
+fn test_soundings_between() {
+    use chrono::NaiveDate;
+
+    let example_file = load("2017040106Z_gfs_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    let start = NaiveDate::from_ymd_opt(2017, 4, 2)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let end = NaiveDate::from_ymd_opt(2017, 4, 2)
+        .unwrap()
+        .and_hms_opt(6, 0, 0)
+        .unwrap();
+
+    // The GFS is every 3 hours, so the inclusive range has 00Z, 03Z, and 06Z.
+    let times: Vec<_> = data
+        .soundings_between(start, end)
+        .map(|(snd, _)| snd.valid_time().unwrap())
+        .collect();
+    assert_eq!(times, vec![start, start + chrono::Duration::hours(3), end]);
+
+    // An empty range.
+    assert_eq!(data.soundings_between(end, start).count(), 0);
+}
+
+#[test]
+fn test_soundings_by_lead() {
+    let example_file = load("2017040100Z_nam_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    // Hourly out to 84 hours, so 0, 12, 24, ..., 84.
+    let leads: Vec<_> = data
+        .soundings_by_lead(|lt| lt % 12 == 0)
+        .map(|(snd, _)| snd.lead_time().unwrap())
+        .collect();
+    assert_eq!(leads, (0..=84).step_by(12).collect::<Vec<_>>());
+
+    assert_eq!(data.soundings_by_lead(|_| true).count(), 85);
+}
+
+#[test]
+fn test_first_sounding() {
+    use chrono::NaiveDate;
+    use metfor::Quantity;
+    use optional::some;
+
+    // The first valid time and the pressure at the lowest level above the surface.
+    for &(fname, hour, pres) in &[
+        ("2017040106Z_gfs_kmso.buf", 6, 906.9),
+        ("2017040100Z_nam_kmso.buf", 0, 867.8),
+    ] {
+        let example_file = load(fname);
+        let data = example_file.data().expect("Error parsing data");
+
+        let (snd, _) = data.first_sounding().expect("Missing first sounding");
+
+        let valid_time =
+            NaiveDate::from_ymd_opt(2017, 4, 1).and_then(|d| d.and_hms_opt(hour, 0, 0));
+        assert_eq!(snd.valid_time(), valid_time);
+        assert_eq!(snd.lead_time().into_option(), Some(0));
+        // The first level is the surface.
+        assert_eq!(snd.pressure_profile()[1].map_t(|p| p.unpack()), some(pres));
+    }
+}
+
+#[test]
+fn test_cloud_fraction_percent() {
+    use metfor::Quantity;
+
+    let example_file = load("2017040100Z_nam_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+    let (snd, _) = data.into_iter().next().unwrap();
+
+    // CFRL in the first sounding is 1.00 at 768.6 hPa and 100.00 at 757.0 hPa.
+    let cloud_at = |pres: f64| {
+        let idx = snd
+            .pressure_profile()
+            .iter()
+            .position(|p| p.map_t(|p| p.unpack()).into_option() == Some(pres))
+            .unwrap();
+        snd.cloud_fraction_profile()[idx].into_option()
+    };
+    assert_eq!(cloud_at(768.6), Some(1.0));
+    assert_eq!(cloud_at(757.0), Some(100.0));
+}
+
+#[test]
+fn test_last_sounding() {
+    for fname in &["2017040106Z_gfs_kmso.buf", "2017040100Z_nam_kmso.buf"] {
+        let example_file = load(fname);
+        let data = example_file.data().expect("Error parsing data");
+
+        let (snd, anal) = data.last_sounding().expect("Missing last sounding");
+        // Walk forward through the whole file, not from the back like last_sounding does.
+        let (expected_snd, expected_anal) = data.into_iter().fold(None, |_, x| Some(x)).unwrap();
+
+        assert_eq!(snd.valid_time(), expected_snd.valid_time());
+        assert_eq!(snd.pressure_profile(), expected_snd.pressure_profile());
+        assert_eq!(
+            snd.temperature_profile(),
+            expected_snd.temperature_profile()
+        );
+        assert_eq!(anal, expected_anal);
+    }
+}
+
+#[test]
+fn test_provider_analysis() {
+    use metfor::{CelsiusDiff, JpKg, Mm};
+    use optional::some;
+    use sounding_bufkit::ProviderAnalysis;
+    use std::collections::HashMap;
+
+    let example_file = load("2017040106Z_gfs_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    for ((_, map), (_, typed)) in data.into_iter().zip(data.analyses_typed()) {
+        assert_eq!(HashMap::from(&ProviderAnalysis::from(&map)), map);
+        assert_eq!(HashMap::from(&typed), map);
+    }
+
+    let (_, map) = data.into_iter().next().unwrap();
+    let typed = ProviderAnalysis::from(&map);
+    assert_eq!(typed.showalter, some(CelsiusDiff(map["Showalter"])));
+    assert_eq!(typed.cape, some(JpKg(map["CAPE"])));
+    assert_eq!(typed.pwat, some(Mm(map["PWAT"])));
+    // This GFS file doesn't have storm motion.
+    assert!(typed.storm_motion.is_none());
+    assert!(!map.contains_key("StormMotionUMps"));
+}
+
+#[test]
+fn test_timed_soundings() {
+    use std::collections::BTreeMap;
+
+    let example_file = load("2017040100Z_nam_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    let by_time: BTreeMap<_, _> = data.timed_soundings().collect();
+    assert_eq!(by_time.len(), data.len());
+
+    let keys: Vec<_> = by_time.keys().copied().collect();
+    assert_eq!(keys, data.valid_times());
+    assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+
+    for (valid_time, snd) in &by_time {
+        assert_eq!(snd.valid_time(), Some(*valid_time));
+    }
+}
+
+#[test]
+fn test_get_index() {
+    use sounding_bufkit::{get_index, keys, AnalysisKey};
+
+    let example_file = load("2017040100Z_nam_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    let (_, map) = data.into_iter().next().unwrap();
+    assert_eq!(get_index(&map, AnalysisKey::Cape), Some(0.78));
+    assert_eq!(AnalysisKey::Cape.name(), keys::CAPE);
+    assert_eq!(
+        get_index(&map, AnalysisKey::Cape),
+        map.get(keys::CAPE).copied()
+    );
+    assert_eq!(get_index(&map, AnalysisKey::EquilibriumLevel), Some(731.95));
+    // The surface data for this file doesn't have a weather symbol.
+    assert!(!map.contains_key(keys::WX_SYMBOL_CODE));
+    assert_eq!(get_index(&map, AnalysisKey::WxSymbolCode), None);
+}
+
+#[test]
+fn test_surface_to_csv() {
+    let example_file = load("2017040106Z_gfs_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    let mut buf: Vec<u8> = vec![];
+    data.surface_to_csv(&mut buf).unwrap();
+    let csv = String::from_utf8(buf).unwrap();
+    let mut lines = csv.lines();
+
+    assert_eq!(
+        lines.next().unwrap(),
+        "valid_time,mslp_hpa,station_pres_hpa,temperature_c,dewpoint_c,wind_dir_deg,\
+         wind_spd_kt,low_cloud,mid_cloud,high_cloud,precip_1hr_mm"
+    );
+    // The GFS has 3-hour precipitation, so there is no 1-hour value.
+    assert_eq!(
+        lines.next().unwrap(),
+        "2017-04-01 06:00,1022.1,909.3,0.94,-1.41,241,2.0,0.01,0,0.36,"
+    );
+    assert_eq!(lines.count(), 60);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_write_jsonl() {
+    let example_file = load("2017040100Z_nam_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    let mut buf: Vec<u8> = vec![];
+    data.write_jsonl(&mut buf).unwrap();
+    let text = String::from_utf8(buf).unwrap();
+
+    let mut count = 0;
+    for line in text.lines() {
+        let val: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(val["station_num"], 727730);
+        assert!(val["pressure"].as_array().unwrap().len() > 50);
+        assert!(val["analysis"]["PWAT"].is_number());
+        count += 1;
+    }
+    assert_eq!(count, data.into_iter().count());
+    assert_eq!(count, 85);
+}
+
+#[test]
+fn test_surface_data() {
+    let example_file = load("2017040106Z_gfs_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    assert_eq!(data.surface_data().count(), 61);
+    assert!(data
+        .surface_data()
+        .all(|sd| sd.station_num == 727730 && sd.mslp.is_some()));
+}
+
+#[test]
+fn test_upper_air() {
+    let example_file = load("2017040106Z_gfs_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    // Every upper air time has matching surface data in this file.
+    assert_eq!(data.upper_air().count(), data.into_iter().count());
+    assert_eq!(data.upper_air().count(), 61);
+    assert!(data.upper_air().all(|ua| ua.validate().is_ok()));
+}
+
+#[test]
+fn test_shared_station_id() {
+    use std::sync::Arc;
+
+    let example_file = load("2017040100Z_nam_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+
+    let ids: Vec<Arc<str>> = data.upper_air().map(|ua| ua.id.unwrap()).collect();
+    assert_eq!(ids.len(), 85);
+    assert!(ids.iter().all(|id| &**id == "KMSO"));
+    assert!(ids.iter().all(|id| Arc::ptr_eq(id, &ids[0])));
+
+    assert!(data
+        .into_iter()
+        .all(|(snd, _)| snd.station_info().station_id() == Some("KMSO")));
+}
+
+#[test]
+fn test_validate_reports_line_number() {
+    use sounding_bufkit::{BufkitData, BufkitFileError};
+
+    let example_file = load("2017040100Z_nam_kmso.buf");
+
+    // Corrupt one value on the given line (starting at 1) and return the line validation reports.
+    let corrupt_line = |line_num: usize, value: &str| -> Option<usize> {
+        let text: Vec<String> = example_file
+            .raw_text()
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                if i + 1 == line_num {
+                    assert!(line.contains(value));
+                    line.replacen(value, "1.x2", 1)
+                } else {
+                    line.to_owned()
+                }
+            })
+            .collect();
+        let text = text.join("\n");
+
+        let data = BufkitData::init(&text, "corrupted").unwrap();
+        let err = data.validate().unwrap_err();
+        err.downcast_ref::<BufkitFileError>()
+            .and_then(|err| err.line())
+    };
+
+    // A value in the profile of the first sounding.
+    assert_eq!(corrupt_line(20, "4.08"), Some(20));
+    // A value in the first row of the surface section.
+    assert_eq!(corrupt_line(11232, "8.84"), Some(11232));
+}
+
+#[test]
+fn test_validate_collect() {
+    use sounding_bufkit::BufkitData;
+
+    let example_file = load("2017040100Z_nam_kmso.buf");
+
+    let data = example_file.data().unwrap();
+    assert!(data.validate_collect().is_empty());
+
+    // Corrupt a value in the profile of the first sounding and one in the first surface row.
+    let text: Vec<String> = example_file
+        .raw_text()
+        .lines()
+        .enumerate()
+        .map(|(i, line)| match i + 1 {
+            20 => line.replacen("4.08", "1.x2", 1),
+            11232 => line.replacen("8.84", "1.x2", 1),
+            _ => line.to_owned(),
+        })
+        .collect();
+    let text = text.join("\n");
+
+    let data = BufkitData::init(&text, "corrupted").unwrap();
+    let errors = data.validate_collect();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].1.line(), Some(20));
+    assert_eq!(errors[1].1.line(), Some(11232));
+    // The lines where the bad sounding and surface record start.
+    assert_eq!(errors[0].0, 5);
+    assert_eq!(errors[1].0, 11230);
+
+    // Iterating still skips only the bad sounding and surface record.
+    assert_eq!(data.into_iter().count(), 84);
+}
+
+#[test]
+fn test_into_validated_data() {
+    let example_file = load("2017040100Z_nam_kmso.buf");
+
+    let data = example_file.data().unwrap();
+    let iterated: Vec<_> = data.into_iter().collect();
+
+    let validated = load("2017040100Z_nam_kmso.buf")
+        .into_validated_data()
+        .unwrap();
+    assert_eq!(validated.len(), 85);
+    assert_eq!(validated.file_name(), "2017040100Z_nam_kmso.buf");
+    assert_eq!(validated.len(), iterated.len());
+
+    for ((snd, anal), (it_snd, it_anal)) in validated.iter().zip(&iterated) {
+        assert_eq!(format!("{:?}", snd), format!("{:?}", it_snd));
+        assert_eq!(anal, it_anal);
+    }
+
+    // The seeded invalid file fails validation.
+    assert!(load("2017040100Z_gfs_kmso.buf")
+        .into_validated_data()
+        .is_err());
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn test_par_soundings() {
+    use rayon::prelude::*;
+
+    for fname in &["2017040100Z_nam_kmso.buf", "2017040100Z_gfs_kmso.buf"] {
+        let example_file = load(fname);
+        let data = example_file.data().unwrap();
+
+        let sequential: Vec<_> = data.into_iter().collect();
+        let parallel: Vec<_> = data.par_soundings().collect();
+        assert_eq!(sequential.len(), parallel.len());
+
+        for ((snd, anal), (par_snd, par_anal)) in sequential.iter().zip(&parallel) {
+            assert_eq!(format!("{:?}", snd), format!("{:?}", par_snd));
+            assert_eq!(anal, par_anal);
+        }
+    }
+}
+
+#[test]
+fn test_from_str() {
+    use sounding_bufkit::BufkitFileError;
+
+    let path = Path::new(EXAMPLE_DIR).join("2017040100Z_nam_kmso.buf");
+    let text = std::fs::read_to_string(path).unwrap();
+
+    let file: BufkitFile = text.parse().unwrap();
+    assert_eq!(file.raw_text(), text);
+    assert_eq!(file.data().unwrap().into_iter().count(), 85);
+
+    let err = "STID = KMSO".parse::<BufkitFile>().err();
+    assert_eq!(err, Some(BufkitFileError::MissingBreakPoint));
+}
+
+#[test]
+fn test_byte_order_mark() {
+    let path = Path::new(EXAMPLE_DIR).join("2017040100Z_nam_kmso.buf");
+    let text = std::fs::read_to_string(path).unwrap();
+
+    let file = BufkitFile::from_string(format!("\u{FEFF}{}", text), "bom.buf".to_owned());
+    let data = file.data().unwrap();
+    assert!(data.validate().is_ok());
+    assert_eq!(data.into_iter().count(), 85);
+
+    let (first, _) = data.into_iter().next().unwrap();
+    assert_eq!(first.station_info().station_id(), Some("KMSO"));
+}
+
+#[test]
+fn test_section_ranges() {
+    let example_file = load("2017040100Z_nam_kmso.buf");
+    let data = example_file.data().expect("Error parsing data");
+    let text = example_file.raw_text();
+
+    let (upper_air, surface) = data.section_ranges();
+    assert_eq!(upper_air.start, 0);
+    assert_eq!(upper_air.end, surface.start);
+    assert_eq!(surface.end, text.len());
+
+    assert!(text[upper_air].trim_start().starts_with("SNPARM = "));
+    assert!(text[surface].starts_with("STN YYMMDD/HHMM"));
+
+    // The byte order mark isn't part of either section.
+    let file = BufkitFile::from_string(format!("\u{FEFF}{}", text), "bom.buf".to_owned());
+    let data = file.data().unwrap();
+    let (upper_air, surface) = data.section_ranges();
+    assert_eq!(upper_air.start, '\u{FEFF}'.len_utf8());
+    assert_eq!(surface.end, file.raw_text().len());
+    assert_eq!(&file.raw_text()[upper_air.start..surface.end], text);
+}
+
+#[test]
+fn test_disjoint_times() {
+    use chrono::NaiveDate;
+    use sounding_bufkit::{BufkitData, BufkitFileError};
+
+    let example_file = load("2017040100Z_nam_kmso.buf");
+
+    // Move every surface record a year later, so no times match the upper air section.
+    let text = example_file.raw_text().replace("727730 17", "727730 18");
+    let data = BufkitData::init(&text, "disjoint").unwrap();
+    assert_eq!(data.into_iter().count(), 0);
+
+    let time = |year, hour| {
+        NaiveDate::from_ymd_opt(year, 4, 1)
+            .and_then(|d| d.and_hms_opt(hour, 0, 0))
+            .unwrap()
+    };
+    let expected = BufkitFileError::DisjointTimes {
+        upper_air: vec![time(2017, 0), time(2017, 1), time(2017, 2)],
+        surface: vec![time(2018, 0), time(2018, 1), time(2018, 2)],
+    };
+
+    let err = data.validate().unwrap_err();
+    assert_eq!(err.downcast_ref::<BufkitFileError>(), Some(&expected));
+    assert!(err.to_string().contains("2017-04-01 00:00Z"));
+    assert!(err.to_string().contains("2018-04-01 00:00Z"));
+
+    let errors = data.validate_collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].1, expected);
+}
+
+#[test]
+fn test_sanity_check() {
+    use chrono::NaiveDate;
+    use sounding_bufkit::BufkitData;
+
+    let example_file = load("2017040100Z_nam_kmso.buf");
+    let data = example_file.data().unwrap();
+    assert!(data.sanity_check().is_empty());
+
+    // The temperature at the lowest level of the first sounding, off by a factor of 10.
+    let text = example_file
+        .raw_text()
+        .replacen("867.80 8.24", "867.80 82.4", 1);
+    let data = BufkitData::init(&text, "bad temperature").unwrap();
+    assert!(data.validate().is_ok());
+
+    let warnings = data.sanity_check();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        warnings[0].valid_time,
+        NaiveDate::from_ymd_opt(2017, 4, 1)
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .unwrap()
+    );
+    assert_eq!(warnings[0].name, "temperature");
+    assert_eq!(warnings[0].level, Some(0));
+    assert_eq!(warnings[0].value, 82.4);
+    assert_eq!(
+        warnings[0].to_string(),
+        "2017-04-01 00:00Z temperature at level 0 is out of range: 82.4"
+    );
+}
+
+#[test]
+fn test_time_step() {
+    use chrono::Duration;
+
+    let time_step = |fname: &str| {
+        let example_file = load(fname);
+        let data = example_file.data().unwrap();
+        data.time_step()
+    };
+
+    assert_eq!(
+        time_step("2017040100Z_nam_kmso.buf"),
+        Some(Duration::hours(1))
+    );
+    assert_eq!(
+        time_step("2017040106Z_gfs_kmso.buf"),
+        Some(Duration::hours(3))
+    );
+}
+
+#[test]
+fn test_station_location() {
+    use metfor::Quantity;
+
+    let example_file = load("2017040100Z_nam_kmso.buf");
+
+    let (lat, lon, elevation) = example_file.station_location().unwrap();
+    assert_eq!(lat.unwrap(), 46.87);
+    assert_eq!(lon.unwrap(), -114.16);
+    assert_eq!(elevation.unwrap().unpack(), 1335.0);
+}
+
+#[test]
+fn test_write_bufkit() {
+    let example_file = load("2017040106Z_gfs_kmso.buf");
+    let data = example_file.data().unwrap();
+
+    let mut text = vec![];
+    data.write_bufkit(&mut text).unwrap();
+
+    let round_trip = BufkitFile::from_string(String::from_utf8(text).unwrap(), "test".to_owned());
+    assert!(round_trip.validate_file_format().is_ok());
+    let round_trip = round_trip.data().unwrap();
+    assert_eq!(round_trip.into_iter().count(), data.into_iter().count());
+    assert_eq!(round_trip.into_iter().count(), 61);
+
+    // Missing values are NaN, so compare the Debug output instead of the values.
+    for (snd, other) in data.upper_air().zip(round_trip.upper_air()) {
+        assert_eq!(format!("{:?}", snd), format!("{:?}", other));
+    }
+}
+
+#[test]
+fn test_subset_by_time() {
+    use chrono::NaiveDate;
+
+    let example_file = load("2017040100Z_nam_kmso.buf");
+    let data = example_file.data().unwrap();
+
+    let time = |hour| {
+        NaiveDate::from_ymd_opt(2017, 4, 1)
+            .and_then(|d| d.and_hms_opt(hour, 0, 0))
+            .unwrap()
+    };
+
+    let subset = BufkitFile::from_string(data.subset_by_time(time(3), time(5)), "test".to_owned());
+    assert!(subset.validate_file_format().is_ok());
+    let subset = subset.data().unwrap();
+    assert_eq!(subset.len(), 3);
+    assert_eq!(subset.valid_times(), vec![time(3), time(4), time(5)]);
+    assert_eq!(subset.into_iter().count(), 3);
+}
+
+#[test]
+fn test_into_soundings() {
+    use sounding_bufkit::OwnedSoundings;
+
+    // The soundings outlive the file they came from.
+    fn load_soundings(fname: &str) -> OwnedSoundings {
+        load(fname).into_soundings().unwrap()
+    }
+
+    let soundings = load_soundings("2017040100Z_nam_kmso.buf");
+    assert_eq!(soundings.len(), 85);
+    assert_eq!(soundings.file_name(), "2017040100Z_nam_kmso.buf");
+
+    // The seeded invalid data is skipped, not an error.
+    let soundings = load_soundings("2017040100Z_gfs_kmso.buf");
+    assert_eq!(soundings.iter().count(), 61);
+}
+
+#[test]
+fn test_into_iter_by_value() {
+    use sounding_bufkit::OwnedSoundings;
+    use std::convert::TryFrom;
+
+    let example_file = load("2017040106Z_gfs_kmso.buf");
+
+    let mut count = 0;
+    for (snd, _anal) in OwnedSoundings::try_from(example_file).unwrap() {
+        assert!(snd.valid_time().is_some());
+        count += 1;
+    }
+    assert_eq!(count, 61);
+
+    // A file that can't be split into sections is an error, not an empty list.
+    let bad = BufkitFile::from_string("Not a bufkit file".to_owned(), "test".to_owned());
+    assert!(OwnedSoundings::try_from(bad).is_err());
+}
+
+#[test]
+fn test_nth() {
+    let example_file = load("2017040106Z_gfs_kmso.buf");
+    let data = example_file.data().unwrap();
+
+    let (snd, anal) = data.into_iter().nth(30).unwrap();
+    #[allow(clippy::iter_skip_next)]
+    let (expected_snd, expected_anal) = data.into_iter().skip(30).next().unwrap();
+
+    // Missing values are NaN, so compare the Debug output instead of the values.
+    assert_eq!(format!("{:?}", snd), format!("{:?}", expected_snd));
+    assert_eq!(anal, expected_anal);
+
+    let mut iter = data.into_iter();
+    assert!(iter.nth(59).is_some());
+    assert!(iter.next().is_some());
+    assert!(iter.next().is_none());
+    assert!(data.into_iter().nth(61).is_none());
+}
+
+#[test]
+fn test_offset_sections() {
+    use chrono::NaiveDate;
+
+    let example_file = load("2017040100Z_nam_kmso.buf");
+    let data = example_file.data().unwrap();
+
+    let time = |hour| {
+        NaiveDate::from_ymd_opt(2017, 4, 1)
+            .and_then(|d| d.and_hms_opt(hour, 0, 0))
+            .unwrap()
+    };
+
+    // All of the upper air section, but the surface section starts 5 hours later and ends early.
+    let subset = data.subset_by_time(time(5), time(20));
+    let upper_air_text =
+        &example_file.raw_text()[..example_file.raw_text().find("STN YYMMDD/HHMM").unwrap()];
+    let surface_text = &subset[subset.find("STN YYMMDD/HHMM").unwrap()..];
+    let offset = BufkitFile::from_string(
+        format!("{}{}", upper_air_text, surface_text),
+        "2017040100Z_nam_kmso.buf".to_owned(),
+    );
+    let offset = offset.data().unwrap();
+
+    let offset: Vec<String> = offset
+        .into_iter()
+        .map(|(snd, _)| format!("{:?}", snd))
+        .collect();
+    let expected: Vec<String> = data
+        .soundings_between(time(5), time(20))
+        .map(|(snd, _)| format!("{:?}", snd))
+        .collect();
+
+    assert_eq!(offset.len(), 16);
+    assert_eq!(offset, expected);
+}
+
+#[test]
+fn test_init_with_options() {
+    use sounding_bufkit::{BufkitData, ParseOptions};
+
+    let example_file = load("2017040100Z_nam_kmso.buf");
+    let data = example_file.data().unwrap();
+
+    // The same file, but with -999 as the missing value.
+    let text = example_file.raw_text().replace("-9999.00", "-999.00");
+    let options = ParseOptions::default().with_missing_value(-999.0);
+    let odd_data = BufkitData::init_with_options(&text, "test", options).unwrap();
+
+    let expected: Vec<String> = data
+        .upper_air()
+        .map(|ua| format!("{:?}", ua))
+        .chain(data.surface_data().map(|sd| format!("{:?}", sd)))
+        .collect();
+    let parsed: Vec<String> = odd_data
+        .upper_air()
+        .map(|ua| format!("{:?}", ua))
+        .chain(odd_data.surface_data().map(|sd| format!("{:?}", sd)))
+        .collect();
+    assert_eq!(parsed, expected);
+
+    // Without the options, -999 is just a number.
+    let default_data = BufkitData::init(&text, "test").unwrap();
+    assert!(default_data
+        .upper_air()
+        .any(|ua| ua.eqlv.map(|p| p.0) == Some(-999.0)));
+}
+
+#[test]
+fn test_merge_soundings() {
+    use chrono::NaiveDate;
+    use sounding_bufkit::{merge_soundings, BufkitData, BufkitFileError};
+
+    let early = load("2017040100Z_nam_kmso.buf");
+    let late = load("2017040112Z_nam_kmso.buf");
+
+    // The order of the files doesn't matter.
+    let files = [late.data().unwrap(), early.data().unwrap()];
+    let merged = merge_soundings(&files).unwrap();
+
+    // The first 12 hours of the 00Z run, then all 85 soundings from the 12Z run, 00Z April 1st
+    // through 00Z April 5th.
+    assert_eq!(merged.len(), 97);
+    let valid_times: Vec<_> = merged
+        .iter()
+        .map(|(snd, _)| snd.valid_time().unwrap())
+        .collect();
+    assert!(valid_times.windows(2).all(|pair| pair[0] < pair[1]));
+    let time = |day, hour| {
+        NaiveDate::from_ymd_opt(2017, 4, day)
+            .and_then(|d| d.and_hms_opt(hour, 0, 0))
+            .unwrap()
+    };
+    assert_eq!(valid_times[0], time(1, 0));
+    assert_eq!(valid_times[96], time(5, 0));
+
+    // Where they overlap, the soundings come from the later run.
+    let overlap = NaiveDate::from_ymd_opt(2017, 4, 1)
+        .and_then(|d| d.and_hms_opt(12, 0, 0))
+        .unwrap();
+    let (snd, _) = merged
+        .iter()
+        .find(|(snd, _)| snd.valid_time() == Some(overlap))
+        .unwrap();
+    assert_eq!(snd.lead_time().into_option(), Some(0));
+    let (first, _) = &merged[0];
+    assert_eq!(first.lead_time().into_option(), Some(0));
+
+    // Soundings from a different station can't be merged.
+    let other_text = early.raw_text().replace("727730", "727731");
+    let other = BufkitData::init(&other_text, "other").unwrap();
+    assert_eq!(
+        merge_soundings(&[late.data().unwrap(), other]).unwrap_err(),
+        BufkitFileError::StationMismatch {
+            expected: 727730,
+            found: 727731,
+        }
+    );
+}
+
+#[test]
+fn test_station_num_mismatch() {
+    use sounding_bufkit::BufkitFileError;
+
+    let path = Path::new(EXAMPLE_DIR).join("2017040100Z_nam_kmso.buf");
+    let text = std::fs::read_to_string(path).unwrap();
+    assert!(BufkitFile::from_string(text.clone(), "good".to_owned())
+        .validate_file_format()
+        .is_ok());
+
+    // Change the station number of one surface record.
+    let text = text.replacen("727730 170401/0500", "727731 170401/0500", 1);
+    let line = text[..text.find("727731 170401/0500").unwrap()]
+        .matches('\n')
+        .count()
+        + 1;
+
+    let file = BufkitFile::from_string(text, "bad".to_owned());
+    let err = file.validate_file_format().unwrap_err();
+    let err = err.downcast_ref::<BufkitFileError>().unwrap();
+    assert_eq!(err.line(), Some(line));
+    assert!(matches!(
+        err,
+        BufkitFileError::AtLine { error, .. }
+            if **error == BufkitFileError::StationMismatch { expected: 727730, found: 727731 }
+    ));
+
+    let data = file.data().unwrap();
+    let errors = data.validate_collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, line);
+
+    assert!(file.into_validated_data().is_err());
+}
+
+#[test]
+fn test_load_directory() {
+    use sounding_bufkit::load_directory;
+
+    // Two overlapping files, and a README that isn't a bufkit file.
+    let dir = Path::new("tests").join("fixtures").join("directory");
+    let soundings = load_directory(&dir).unwrap();
+
+    let hours: Vec<u32> = soundings
+        .iter()
+        .map(|(snd, _)| chrono::Timelike::hour(&snd.valid_time().unwrap()))
+        .collect();
+    assert_eq!(hours, vec![10, 11, 12, 13, 14]);
+
+    // The overlap comes from the later run.
+    let lead_times: Vec<Option<i32>> = soundings
+        .iter()
+        .map(|(snd, _)| snd.lead_time().into_option())
+        .collect();
+    assert_eq!(
+        lead_times,
+        vec![Some(10), Some(11), Some(0), Some(1), Some(2)]
+    );
+
+    assert!(load_directory(&dir.join("not_there")).is_err());
+}